@@ -9,48 +9,53 @@ struct Example {
 }
 
 unsafe impl MemConstruct for Example {
-    type Constructor = ExampleConstructor<MemConstructExamplex, MemConstructExampley>;
-    type ConstructorFinishedToken = ExampleConstructor<(), ()>;
+    type Constructor<'b> = ExampleConstructor<'b, MemConstructExamplex, MemConstructExampley>;
+    type ConstructorFinishedToken<'b> = ExampleConstructor<'b, (), ()>;
 }
 
-pub struct ExampleConstructor<T0, T1> {
+pub struct ExampleConstructor<'b, T0, T1> {
     ptr: *mut Example,
     boo_scary: PhantomData<(T0, T1)>,
+    // The brand makes the constructor invariant over 'b
+    brand: PhantomData<fn(&'b ()) -> &'b ()>,
 }
 
 unsafe impl MemConstructConstructor
-    for ExampleConstructor<MemConstructExamplex, MemConstructExampley>
+    for ExampleConstructor<'_, MemConstructExamplex, MemConstructExampley>
 {
     type Target = Example;
 
     unsafe fn new(ptr: *mut Self::Target) -> Self {
         Self {
             ptr,
-            boo_scary: PhantomData::default(),
+            boo_scary: PhantomData,
+            brand: PhantomData,
         }
     }
 }
 
-impl<T0> ExampleConstructor<MemConstructExamplex, T0> {
-    pub fn set_x(self, x: i32) -> ExampleConstructor<(), T0> {
+impl<'b, T0> ExampleConstructor<'b, MemConstructExamplex, T0> {
+    pub fn set_x(self, x: i32) -> ExampleConstructor<'b, (), T0> {
         unsafe {
             ptr::addr_of_mut!((*self.ptr).x).write(x);
         }
         ExampleConstructor::<(), T0> {
             ptr: self.ptr,
-            boo_scary: PhantomData::default(),
+            boo_scary: PhantomData,
+            brand: PhantomData,
         }
     }
 }
 
-impl<T0> ExampleConstructor<T0, MemConstructExampley> {
-    pub fn set_y(self, y: i32) -> ExampleConstructor<T0, ()> {
+impl<'b, T0> ExampleConstructor<'b, T0, MemConstructExampley> {
+    pub fn set_y(self, y: i32) -> ExampleConstructor<'b, T0, ()> {
         unsafe {
             ptr::addr_of_mut!((*self.ptr).y).write(y);
         }
         ExampleConstructor::<T0, ()> {
             ptr: self.ptr,
-            boo_scary: PhantomData::default(),
+            boo_scary: PhantomData,
+            brand: PhantomData,
         }
     }
 }
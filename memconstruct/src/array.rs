@@ -1,55 +1,92 @@
-/// Memconstruct Implementation on arrays
-///
-/// TODO write about implementation on arrays
-use core::{marker::PhantomData, mem, panic::AssertUnwindSafe, ptr};
+//! Memconstruct Implementation on arrays
+//!
+//! TODO write about implementation on arrays
+use core::{marker::PhantomData, mem, ptr};
 
-use crate::{primitive::MemconstructPrimitive, util, MemConstruct, MemConstructConstructor};
+use crate::{
+    primitive::MemconstructPrimitive,
+    util::DropGuard,
+    zeroed::{Zeroable, ZeroedConstruct},
+    ConstructFnMut, FieldToken, MemConstruct, MemConstructConstructor,
+};
 
 pub struct ArrayTok;
 
-pub struct ArrayMemConstructor<Tok, T, const N: usize> {
+unsafe impl FieldToken for ArrayTok {
+    const IS_SET: bool = false;
+}
+
+pub struct ArrayMemConstructor<'b, Tok: FieldToken, T, const N: usize> {
     ptr: *mut [T; N],
     boo_scary: PhantomData<Tok>,
+    /// Invariant lifetime brand of the constructor
+    brand: PhantomData<fn(&'b ()) -> &'b ()>,
+}
+
+impl<Tok: FieldToken, T, const N: usize> Drop for ArrayMemConstructor<'_, Tok, T, N> {
+    fn drop(&mut self) {
+        if Tok::IS_SET {
+            // SAFETY: The token says that all elements were initialized
+            unsafe {
+                ptr::drop_in_place(self.ptr);
+            }
+        }
+    }
 }
 
 unsafe impl<T, const N: usize> MemConstruct for [T; N] {
-    type Constructor = ArrayMemConstructor<ArrayTok, T, N>;
-    type ConstructorFinishedToken = ArrayMemConstructor<(), T, N>;
+    type Constructor<'b> = ArrayMemConstructor<'b, ArrayTok, T, N>;
+    type ConstructorFinishedToken<'b> = ArrayMemConstructor<'b, (), T, N>;
 }
 
-unsafe impl<T, const N: usize> MemConstructConstructor for ArrayMemConstructor<ArrayTok, T, N> {
+unsafe impl<T: Zeroable, const N: usize> ZeroedConstruct for [T; N] {
+    type ZeroedConstructor<'b> = ArrayMemConstructor<'b, (), T, N>;
+
+    unsafe fn zeroed_constructor<'b>(ptr: *mut Self) -> Self::ZeroedConstructor<'b> {
+        ArrayMemConstructor {
+            ptr,
+            boo_scary: PhantomData,
+            brand: PhantomData,
+        }
+    }
+}
+
+unsafe impl<T, const N: usize> MemConstructConstructor
+    for ArrayMemConstructor<'_, ArrayTok, T, N>
+{
     type Target = [T; N];
 
     unsafe fn new(ptr: *mut Self::Target) -> Self {
         Self {
             ptr,
-            boo_scary: PhantomData::default(),
+            boo_scary: PhantomData,
+            brand: PhantomData,
         }
     }
 }
 
-impl<T, const N: usize> ArrayMemConstructor<ArrayTok, T, N> {
+impl<'b, T, const N: usize> ArrayMemConstructor<'b, ArrayTok, T, N> {
     #[inline(always)]
-    pub fn set_all<F: FnMut(usize) -> T>(self, mut f: F) -> ArrayMemConstructor<(), T, N> {
+    pub fn set_all<F: FnMut(usize) -> T>(self, mut f: F) -> ArrayMemConstructor<'b, (), T, N> {
         if mem::needs_drop::<T>() {
-            unsafe { self.init_all_with_drop(|ptr, i| *ptr = f(i)) }
+            unsafe { self.init_all_with_drop(|ptr, i| ptr.write(f(i))) }
         } else {
-            unsafe { self.init_all_nodrop(|ptr, i| *ptr = f(i)) }
+            unsafe { self.init_all_nodrop(|ptr, i| ptr.write(f(i))) }
         }
     }
 
     #[inline(always)]
-    pub fn memconstruct_all<F: FnMut(T::Constructor) -> T::ConstructorFinishedToken>(
+    pub fn memconstruct_all<F: for<'c> ConstructFnMut<'c, T>>(
         self,
         mut f: F,
-    ) -> ArrayMemConstructor<(), T, N>
+    ) -> ArrayMemConstructor<'b, (), T, N>
     where
         T: MemConstruct,
     {
         if mem::needs_drop::<T>() {
-            unsafe { self.init_all_with_drop(|ptr, _| { f(T::Constructor::new(ptr)); }) }
+            unsafe { self.init_all_with_drop(|ptr, _| { mem::forget(f(T::Constructor::new(ptr))); }) }
         } else {
-            unsafe { self.init_all_nodrop(|ptr, _| { f(T::Constructor::new(ptr)); }) }
+            unsafe { self.init_all_nodrop(|ptr, _| { mem::forget(f(T::Constructor::new(ptr))); }) }
         }
     }
 
@@ -57,53 +94,39 @@ impl<T, const N: usize> ArrayMemConstructor<ArrayTok, T, N> {
     unsafe fn init_all_with_drop<F: FnMut(*mut T, usize)>(
         self,
         mut f: F,
-    ) -> ArrayMemConstructor<(), T, N> where
+    ) -> ArrayMemConstructor<'b, (), T, N> where
     {
-        let mut i = 0usize;
+        // Drops the elements which were already initialized if `f` panics, this also works
+        // without catching the panic
+        let mut guard = DropGuard {
+            start: self.ptr as *mut T,
+            initialized: 0,
+        };
         let mut cur = self.ptr as *mut T;
-        let res = util::catch_unwind(AssertUnwindSafe(|| {
-            for _ in 0..N {
-                // The pointer will be inside the allocation of the array. We will break
-                // after offseting N times so the last access we do is arr[N-1]
-                f(cur, i);
-                // SAFETY: The type system guarantees that we have N entries in the array
-                unsafe {
-                    cur = cur.offset(1);
-                }
-                // We know we can't overflow as N can be usize::MAX at max and we only increment up
-                // to N times.
-                i = i.wrapping_add(1);
-            }
-        }));
-
-        match res {
-            Ok(_) => ArrayMemConstructor {
-                ptr: self.ptr,
-                boo_scary: PhantomData::default(),
-            },
-            Err(e) => {
-                let mut cur = self.ptr as *mut T;
-                for _ in 0..i {
-                    // SAFETY: We only drop the values we know have been initialized
-                    unsafe {
-                        ptr::drop_in_place(cur);
-                    }
-                    // SAFETY: We drop at most i elements i is only incremented up until N so we know
-                    // that i < N holds
-                    unsafe {
-                        cur = cur.offset(1);
-                    }
-                }
-                util::resume_unwind(e)
+        for i in 0..N {
+            // The pointer will be inside the allocation of the array. We will break
+            // after offseting N times so the last access we do is arr[N-1]
+            f(cur, i);
+            guard.initialized = i + 1;
+            // SAFETY: The type system guarantees that we have N entries in the array
+            unsafe {
+                cur = cur.offset(1);
             }
         }
+        mem::forget(guard);
+
+        ArrayMemConstructor {
+            ptr: self.ptr,
+            boo_scary: PhantomData,
+            brand: PhantomData,
+        }
     }
 
     #[inline(always)]
     unsafe fn init_all_nodrop<F: FnMut(*mut T, usize)>(
         self,
         mut f: F,
-    ) -> ArrayMemConstructor<(), T, N>
+    ) -> ArrayMemConstructor<'b, (), T, N>
     {
         let mut cur = self.ptr as *mut T;
         for i in 0..N {
@@ -118,37 +141,42 @@ impl<T, const N: usize> ArrayMemConstructor<ArrayTok, T, N> {
 
         ArrayMemConstructor {
             ptr: self.ptr,
-            boo_scary: PhantomData::default(),
+            boo_scary: PhantomData,
+            brand: PhantomData,
         }
     }
 
     #[inline(always)]
-    pub fn memset(self, byte: u8) -> ArrayMemConstructor<(), T, N>
+    pub fn memset(self, byte: u8) -> ArrayMemConstructor<'b, (), T, N>
     where
         T: MemconstructPrimitive,
     {
-        match mem::size_of::<T>().checked_mul(N) {
-            Some(count) => {
-                // SAFETY: We write sizeof(T) * N bytes, the type systems says we have that many bytes
-                unsafe {
-                    self.ptr.write_bytes(byte, count);
-                }
-                ArrayMemConstructor {
-                    ptr: self.ptr,
-                    boo_scary: PhantomData::default(),
-                }
-            }
-            None => {
-                todo!("Split work across multiple memsets");
-            }
+        // SAFETY: We write N elements of T, the type systems says we have that many elements.
+        // `write_bytes` counts in elements so the byte count can't overflow.
+        unsafe {
+            (self.ptr as *mut T).write_bytes(byte, N);
+        }
+        ArrayMemConstructor {
+            ptr: self.ptr,
+            boo_scary: PhantomData,
+            brand: PhantomData,
         }
     }
 
-    pub unsafe fn with_ptr(self, f: impl FnOnce(*mut [T; N])) -> ArrayMemConstructor<(), T, N> {
+    /// Initialize the whole array through a raw pointer.
+    ///
+    /// # Safety
+    ///
+    /// The passed function has to initialize all `N` elements of the array behind the pointer.
+    pub unsafe fn with_ptr(
+        self,
+        f: impl FnOnce(*mut [T; N]),
+    ) -> ArrayMemConstructor<'b, (), T, N> {
         f(self.ptr);
         ArrayMemConstructor {
             ptr: self.ptr,
-            boo_scary: PhantomData::default(),
+            boo_scary: PhantomData,
+            brand: PhantomData,
         }
     }
 }
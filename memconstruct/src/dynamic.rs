@@ -0,0 +1,43 @@
+//! Support for the runtime checked constructors generated with `#[memconstruct(dynamic)]`
+//!
+//! Dynamic constructors are meant for structs with a lot of fields, where one generic parameter
+//! per field slows compilation down too much. They can be used with
+//! [`HeapConstructExt::try_heapconstruct_fallible`](crate::HeapConstructExt) by returning the
+//! result of `finish`, missing fields are then reported as a `ConstructFailed` error.
+
+use alloc::vec::Vec;
+use core::fmt;
+
+/// The fields which were never set when a dynamic constructor was finished.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingFields {
+    fields: Vec<&'static str>,
+}
+
+impl MissingFields {
+    #[doc(hidden)]
+    pub fn new(fields: Vec<&'static str>) -> Self {
+        Self { fields }
+    }
+
+    /// The names of the missing fields in declaration order, tuple fields are named by index
+    pub fn fields(&self) -> &[&'static str] {
+        &self.fields
+    }
+}
+
+impl fmt::Display for MissingFields {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "construction finished with unset fields: ")?;
+        for (i, field) in self.fields.iter().enumerate() {
+            if i != 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "`{}`", field)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MissingFields {}
@@ -0,0 +1,49 @@
+//! Errors of the fallible field setters
+//!
+//! The generated `try_set_<field>` and `try_construct_<field>` functions fail with a
+//! [`FieldError`], which names the field that couldn't be set. Returning it from a construction
+//! with `HeapConstructExt::try_heapconstruct_fallible` reports it as a `ConstructFailed` error.
+
+use core::fmt;
+
+/// The error of a field that couldn't be set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldError<E> {
+    field: &'static str,
+    error: E,
+}
+
+impl<E> FieldError<E> {
+    #[doc(hidden)]
+    pub fn new(field: &'static str, error: E) -> Self {
+        Self { field, error }
+    }
+
+    /// The name of the field, tuple fields are named by index
+    pub fn field(&self) -> &'static str {
+        self.field
+    }
+
+    /// The error the field failed with
+    pub fn error(&self) -> &E {
+        &self.error
+    }
+
+    /// Take the error the field failed with
+    pub fn into_error(self) -> E {
+        self.error
+    }
+}
+
+impl<E> fmt::Display for FieldError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "setting the field `{}` failed", self.field)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: std::error::Error + 'static> std::error::Error for FieldError<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
@@ -1,19 +1,61 @@
-use alloc::alloc::{alloc as do_alloc, dealloc as do_dealloc};
+use alloc::{
+    alloc::{alloc as do_alloc, alloc_zeroed, handle_alloc_error},
+    boxed::Box,
+};
+#[cfg(feature = "std")]
+use core::any::Any;
 use core::{
     alloc::Layout,
+    any,
+    convert::Infallible,
+    fmt,
     mem,
     panic::AssertUnwindSafe,
-    ptr,
+    ptr::NonNull,
 };
 
-use crate::{util, MemConstruct, MemConstructConstructor};
+use crate::{
+    util::{self, DeallocGuard},
+    zeroed::{TryZeroedConstructFn, ZeroedConstruct, ZeroedConstructFn},
+    ConstructFn, MemConstruct, MemConstructConstructor, TryConstructFn,
+};
 
 pub trait HeapConstruct<T> {
+    /// Allocate memory for a `T` and initialize it through the passed pointer.
+    ///
+    /// A failing allocation is reported as [`HeapConstructError::AllocationFailure`].
+    /// Implementations whose layout depends on a runtime length report lengths whose layout can't
+    /// be computed as [`HeapConstructError::LayoutOverflow`], the layout of a `T` always fits.
+    ///
+    /// # Safety
+    ///
+    /// If `construct` returns `Ok` the value behind the pointer has to be fully initialized.
     unsafe fn try_heapconstruct_fallible_raw<E, F: FnOnce(*mut T) -> Result<(), E>>(
         construct: F,
     ) -> Result<Self, HeapConstructError<E>>
     where
         Self: Sized;
+
+    /// Like [`try_heapconstruct_fallible_raw`](Self::try_heapconstruct_fallible_raw), but the
+    /// memory behind the pointer is zeroed.
+    ///
+    /// The default implementation zeroes the memory before calling `construct`, implementations
+    /// should request zeroed memory from their allocator instead.
+    ///
+    /// # Safety
+    ///
+    /// If `construct` returns `Ok` the value behind the pointer has to be fully initialized.
+    unsafe fn try_heapconstruct_zeroed_fallible_raw<E, F: FnOnce(*mut T) -> Result<(), E>>(
+        construct: F,
+    ) -> Result<Self, HeapConstructError<E>>
+    where
+        Self: Sized,
+    {
+        Self::try_heapconstruct_fallible_raw(|ptr| {
+            ptr.write_bytes(0, 1);
+            construct(ptr)
+        })
+    }
 }
 
 pub trait HeapConstructExt<T>: HeapConstruct<T>
@@ -24,68 +66,148 @@ where
     fn try_heapconstruct_fallible<E, F>(construct: F) -> Result<Self, HeapConstructError<E>>
     where
         Self: Sized,
-        F: FnOnce(T::Constructor) -> Result<T::ConstructorFinishedToken, E>,
+        F: for<'b> TryConstructFn<'b, T, E>,
     {
         unsafe {
             Self::try_heapconstruct_fallible_raw(|ptr| {
-                construct(T::Constructor::new(ptr)).map(|_| ())
+                construct(T::Constructor::new(ptr)).map(mem::forget)
             })
         }
     }
 
     #[inline(always)]
-    fn try_heapconstruct<F>(construct: F) -> Result<Self, HeapConstructError<()>>
+    fn try_heapconstruct<F>(construct: F) -> Result<Self, HeapConstructError<Infallible>>
     where
         Self: Sized,
-        F: FnOnce(T::Constructor) -> T::ConstructorFinishedToken,
+        F: for<'b> ConstructFn<'b, T>,
     {
         unsafe {
             Self::try_heapconstruct_fallible_raw(|ptr| {
-                construct(T::Constructor::new(ptr));
+                mem::forget(construct(T::Constructor::new(ptr)));
                 Ok(())
             })
         }
     }
 
+    /// Like [`try_heapconstruct_fallible`](Self::try_heapconstruct_fallible), but failing
+    /// allocations are handled like in the rest of `alloc`.
+    ///
+    /// # Panics
+    ///
+    /// Calls [`handle_alloc_error`] if the allocation fails and panics if the layout of the value
+    /// overflows.
     #[inline(always)]
     fn heapconstruct_fallible<E, F>(construct: F) -> Result<Self, HeapConstructError<E>>
     where
         Self: Sized,
-        F: FnOnce(T::Constructor) -> Result<T::ConstructorFinishedToken, E>,
+        F: for<'b> TryConstructFn<'b, T, E>,
     {
-        let res = unsafe {
-            Self::try_heapconstruct_fallible_raw(|ptr| {
-                construct(T::Constructor::new(ptr)).map(|_| ())
-            })
-        };
-
-        match res {
+        match Self::try_heapconstruct_fallible(construct) {
             Ok(val) => Ok(val),
-            Err(HeapConstructError::AllocationFailure) => panic!("Allocation failed"),
+            Err(HeapConstructError::AllocationFailure { layout, .. }) => handle_alloc_error(layout),
+            Err(e @ HeapConstructError::LayoutOverflow { .. }) => panic!("{}", e),
             Err(e) => Err(e),
         }
     }
 
+    /// Construct the value on the heap, failing allocations are handled like in the rest of
+    /// `alloc`.
+    ///
+    /// # Panics
+    ///
+    /// Calls [`handle_alloc_error`] if the allocation fails and panics if the layout of the value
+    /// overflows. Panics of `construct` are resumed after the allocation was freed.
     #[inline(always)]
     fn heapconstruct<F>(construct: F) -> Self
     where
         Self: Sized,
-        F: FnOnce(T::Constructor) -> T::ConstructorFinishedToken,
+        F: for<'b> ConstructFn<'b, T>,
     {
-        let res = unsafe {
-            Self::try_heapconstruct_fallible_raw(|ptr| {
-                construct(T::Constructor::new(ptr));
+        match Self::try_heapconstruct(construct) {
+            Ok(val) => val,
+            Err(HeapConstructError::AllocationFailure { layout, .. }) => handle_alloc_error(layout),
+            Err(e @ HeapConstructError::LayoutOverflow { .. }) => panic!("{}", e),
+            Err(HeapConstructError::ConstructPanicked(e)) => util::resume_unwind(e),
+            Err(HeapConstructError::ConstructFailed(never)) => match never {},
+        }
+    }
+
+    /// Construct the value in zeroed memory, the parts of it which are valid when zeroed are
+    /// already set in the [`ZeroedConstructor`](ZeroedConstruct::ZeroedConstructor).
+    #[inline(always)]
+    fn try_heapconstruct_zeroed_fallible<E, F>(construct: F) -> Result<Self, HeapConstructError<E>>
+    where
+        Self: Sized,
+        T: ZeroedConstruct,
+        F: for<'b> TryZeroedConstructFn<'b, T, E>,
+    {
+        unsafe {
+            Self::try_heapconstruct_zeroed_fallible_raw(|ptr| {
+                construct(T::zeroed_constructor(ptr)).map(mem::forget)
+            })
+        }
+    }
+
+    #[inline(always)]
+    fn try_heapconstruct_zeroed<F>(construct: F) -> Result<Self, HeapConstructError<Infallible>>
+    where
+        Self: Sized,
+        T: ZeroedConstruct,
+        F: for<'b> ZeroedConstructFn<'b, T>,
+    {
+        unsafe {
+            Self::try_heapconstruct_zeroed_fallible_raw(|ptr| {
+                mem::forget(construct(T::zeroed_constructor(ptr)));
                 Ok(())
             })
-        };
+        }
+    }
+
+    /// Like [`try_heapconstruct_zeroed_fallible`](Self::try_heapconstruct_zeroed_fallible), but
+    /// failing allocations are handled like in the rest of `alloc`.
+    ///
+    /// # Panics
+    ///
+    /// Calls [`handle_alloc_error`] if the allocation fails and panics if the layout of the value
+    /// overflows.
+    #[inline(always)]
+    fn heapconstruct_zeroed_fallible<E, F>(construct: F) -> Result<Self, HeapConstructError<E>>
+    where
+        Self: Sized,
+        T: ZeroedConstruct,
+        F: for<'b> TryZeroedConstructFn<'b, T, E>,
+    {
+        match Self::try_heapconstruct_zeroed_fallible(construct) {
+            Ok(val) => Ok(val),
+            Err(HeapConstructError::AllocationFailure { layout, .. }) => handle_alloc_error(layout),
+            Err(e @ HeapConstructError::LayoutOverflow { .. }) => panic!("{}", e),
+            Err(e) => Err(e),
+        }
+    }
 
-        match res {
+    /// Construct the value in zeroed memory, failing allocations are handled like in the rest of
+    /// `alloc`.
+    ///
+    /// For `Box` the memory comes from [`alloc_zeroed`], which can hand out memory the operating
+    /// system already zeroed lazily instead of writing the zeros.
+    ///
+    /// # Panics
+    ///
+    /// Calls [`handle_alloc_error`] if the allocation fails and panics if the layout of the value
+    /// overflows. Panics of `construct` are resumed after the allocation was freed.
+    #[inline(always)]
+    fn heapconstruct_zeroed<F>(construct: F) -> Self
+    where
+        Self: Sized,
+        T: ZeroedConstruct,
+        F: for<'b> ZeroedConstructFn<'b, T>,
+    {
+        match Self::try_heapconstruct_zeroed(construct) {
             Ok(val) => val,
-            Err(HeapConstructError::AllocationFailure) => panic!("Allocation failed"),
+            Err(HeapConstructError::AllocationFailure { layout, .. }) => handle_alloc_error(layout),
+            Err(e @ HeapConstructError::LayoutOverflow { .. }) => panic!("{}", e),
             Err(HeapConstructError::ConstructPanicked(e)) => util::resume_unwind(e),
-            Err(HeapConstructError::ConstructFailed(())) => {
-                unreachable!("The construction is not fallible")
-            }
+            Err(HeapConstructError::ConstructFailed(never)) => match never {},
         }
     }
 }
@@ -105,55 +227,197 @@ where
     unsafe fn try_heapconstruct_fallible_raw<E, F: FnOnce(*mut T) -> Result<(), E>>(
         construct: F,
     ) -> Result<Self, HeapConstructError<E>> {
-        if mem::size_of::<T>() == 0usize {
-            let res = util::catch_unwind(AssertUnwindSafe(|| {
-                // Constructors for ZSTs MUST ignore the given pointer here because `alloc`
-                // will fail for allocations of size 0
-                construct(ptr::null_mut())?;
-                Ok(())
-            }));
-
-            return match res {
-                Ok(Ok(_)) => Ok(T::new_boxed_zst()),
-                Ok(Err(e)) => Err(HeapConstructError::ConstructFailed(e)),
-                Err(e) => Err(HeapConstructError::ConstructPanicked(e)),
-            };
-        }
-
-        let layout = Layout::new::<T>();
-        let ptr = unsafe { do_alloc(layout) as *mut T };
+        box_construct(do_alloc, construct)
+    }
 
-        if ptr.is_null() {
-            return Err(HeapConstructError::AllocationFailure);
-        }
+    #[inline(always)]
+    unsafe fn try_heapconstruct_zeroed_fallible_raw<E, F: FnOnce(*mut T) -> Result<(), E>>(
+        construct: F,
+    ) -> Result<Self, HeapConstructError<E>> {
+        box_construct(alloc_zeroed, construct)
+    }
+}
 
+/// Construct a `Box` in memory allocated with `allocate`.
+#[inline(always)]
+unsafe fn box_construct<T, E, F: FnOnce(*mut T) -> Result<(), E>>(
+    allocate: unsafe fn(Layout) -> *mut u8,
+    construct: F,
+) -> Result<Box<T>, HeapConstructError<E>> {
+    if mem::size_of::<T>() == 0usize {
+        // Allocations of size 0 are not allowed, ZSTs live at any well aligned address which
+        // is also how `Box` stores them
+        let ptr = NonNull::<T>::dangling().as_ptr();
         let res = util::catch_unwind(AssertUnwindSafe(|| {
             construct(ptr)?;
             Ok(())
         }));
 
-        match res {
+        return match res {
             Ok(Ok(_)) => unsafe { Ok(Box::from_raw(ptr)) },
-            Ok(Err(e)) => {
-                unsafe { do_dealloc(ptr as *mut u8, layout) };
-                Err(HeapConstructError::ConstructFailed(e))
-            }
-            Err(e) => {
-                unsafe { do_dealloc(ptr as *mut u8, layout) };
-                Err(HeapConstructError::ConstructPanicked(e))
-            }
+            Ok(Err(e)) => Err(HeapConstructError::ConstructFailed(e)),
+            Err(e) => Err(HeapConstructError::ConstructPanicked(e)),
+        };
+    }
+
+    let layout = Layout::new::<T>();
+    let ptr = unsafe { allocate(layout) as *mut T };
+
+    if ptr.is_null() {
+        return Err(HeapConstructError::AllocationFailure {
+            layout,
+            type_name: any::type_name::<T>(),
+        });
+    }
+
+    // Frees the allocation if the construction fails, this also works if the panic isn't
+    // caught
+    let guard = DeallocGuard {
+        ptr: ptr as *mut u8,
+        layout,
+    };
+    let res = util::catch_unwind(AssertUnwindSafe(|| {
+        construct(ptr)?;
+        Ok(())
+    }));
+
+    match res {
+        Ok(Ok(_)) => {
+            mem::forget(guard);
+            unsafe { Ok(Box::from_raw(ptr)) }
         }
+        Ok(Err(e)) => Err(HeapConstructError::ConstructFailed(e)),
+        Err(e) => Err(HeapConstructError::ConstructPanicked(e)),
     }
 }
 
-pub fn construct_box<T: MemConstruct, F: FnOnce(T::Constructor) -> T::ConstructorFinishedToken>(
-    construct: F,
-) -> Box<T> {
+pub fn construct_box<T: MemConstruct, F: for<'b> ConstructFn<'b, T>>(construct: F) -> Box<T> {
     Box::heapconstruct(construct)
 }
 
+/// The error of a heap construction.
+#[derive(Debug)]
 pub enum HeapConstructError<E> {
-    AllocationFailure,
-    ConstructPanicked(util::UnwindError),
+    /// The memory for the value couldn't be allocated
+    AllocationFailure {
+        /// The layout of the requested allocation
+        layout: Layout,
+        /// The name of the constructed type
+        type_name: &'static str,
+    },
+    /// The size of the value, rounded up to its alignment, exceeds `isize::MAX`
+    ///
+    /// Only values with a runtime length can overflow, this is never reported for a `Box<T>`.
+    LayoutOverflow {
+        /// The name of the constructed type
+        type_name: &'static str,
+    },
+    /// The construction function panicked, only reported with the `std` feature
+    ConstructPanicked(PanicPayload),
+    /// The fallible construction function returned an error
     ConstructFailed(E),
 }
+
+impl<E> HeapConstructError<E> {
+    /// Map the error of a failed construction, leaving the other errors untouched
+    pub fn map_err<F, O: FnOnce(E) -> F>(self, op: O) -> HeapConstructError<F> {
+        match self {
+            Self::AllocationFailure { layout, type_name } => {
+                HeapConstructError::AllocationFailure { layout, type_name }
+            }
+            Self::LayoutOverflow { type_name } => HeapConstructError::LayoutOverflow { type_name },
+            Self::ConstructPanicked(payload) => HeapConstructError::ConstructPanicked(payload),
+            Self::ConstructFailed(e) => HeapConstructError::ConstructFailed(op(e)),
+        }
+    }
+
+    /// The error of a failed construction, `None` for the other errors
+    pub fn into_inner(self) -> Option<E> {
+        match self {
+            Self::ConstructFailed(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl<E> fmt::Display for HeapConstructError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::AllocationFailure { layout, type_name } => write!(
+                f,
+                "allocating {} bytes with alignment {} for `{}` failed",
+                layout.size(),
+                layout.align(),
+                type_name,
+            ),
+            Self::LayoutOverflow { type_name } => {
+                write!(f, "the layout of `{}` overflows", type_name)
+            }
+            Self::ConstructPanicked(_) => write!(f, "the construction panicked"),
+            Self::ConstructFailed(_) => write!(f, "the construction failed"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: std::error::Error + 'static> std::error::Error for HeapConstructError<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::ConstructFailed(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// The payload of a panic which was caught during the construction.
+///
+/// Panics are only caught with the `std` feature, without it this type can't be created.
+pub struct PanicPayload {
+    #[cfg(feature = "std")]
+    payload: Box<dyn Any + Send>,
+    #[cfg(not(feature = "std"))]
+    never: core::convert::Infallible,
+}
+
+impl fmt::Debug for PanicPayload {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut tuple = f.debug_tuple("PanicPayload");
+        // Panics with a message carry a `&str` or a `String` as payload
+        #[cfg(feature = "std")]
+        if let Some(message) = self.payload.downcast_ref::<&str>() {
+            tuple.field(message);
+        } else if let Some(message) = self.payload.downcast_ref::<std::string::String>() {
+            tuple.field(message);
+        }
+        tuple.finish_non_exhaustive()
+    }
+}
+
+impl PanicPayload {
+    #[cfg(feature = "std")]
+    pub(crate) fn new(payload: Box<dyn Any + Send>) -> Self {
+        Self { payload }
+    }
+
+    /// The payload the construction panicked with
+    #[cfg(feature = "std")]
+    pub fn get(&self) -> &(dyn Any + Send) {
+        &*self.payload
+    }
+
+    /// Take the payload the construction panicked with
+    #[cfg(feature = "std")]
+    pub fn into_inner(self) -> Box<dyn Any + Send> {
+        self.payload
+    }
+
+    /// Continue the original panic
+    pub fn resume(self) -> ! {
+        #[cfg(feature = "std")]
+        {
+            std::panic::resume_unwind(self.payload)
+        }
+        #[cfg(not(feature = "std"))]
+        match self.never {}
+    }
+}
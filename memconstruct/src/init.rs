@@ -0,0 +1,115 @@
+//! Safe initialization of a single field through its uninitialized memory
+//!
+//! The generated `init_<field>` functions hand out a [`Slot`] for the memory of the field. The
+//! field counts as set once the passed function returns the [`Init`] proof obtained from writing
+//! that slot. Both are branded with the same unique lifetime, so the proof can't come from any
+//! other slot.
+
+use core::{
+    marker::PhantomData,
+    mem::MaybeUninit,
+    ops::{Deref, DerefMut},
+};
+
+use crate::{ConstructFn, MemConstruct};
+
+/// Invariant lifetime brand shared by a [`Slot`] and the [`Init`] created from it
+type Brand<'s> = PhantomData<fn(&'s ()) -> &'s ()>;
+
+/// The uninitialized memory of a single field.
+///
+/// The slot dereferences to the underlying [`MaybeUninit`].
+pub struct Slot<'s, T> {
+    uninit: &'s mut MaybeUninit<T>,
+    brand: Brand<'s>,
+}
+
+/// Proof that the [`Slot`] with the same brand was initialized.
+///
+/// The proof dereferences to the initialized value, which allows to fill it further in place.
+pub struct Init<'s, T> {
+    value: &'s mut T,
+    brand: Brand<'s>,
+}
+
+impl<'s, T> Slot<'s, T> {
+    /// Create a new slot.
+    ///
+    /// # Safety
+    ///
+    /// The pointer has to be valid for writes and well aligned for `'s`. The brand `'s` has to be
+    /// unique to this slot, which is the case if the slot is passed to a function that is generic
+    /// over it.
+    #[doc(hidden)]
+    #[inline(always)]
+    pub unsafe fn new(ptr: *mut MaybeUninit<T>) -> Self {
+        Self {
+            uninit: &mut *ptr,
+            brand: PhantomData,
+        }
+    }
+
+    /// Initialize the slot with a value
+    #[inline(always)]
+    pub fn write(self, val: T) -> Init<'s, T> {
+        Init {
+            value: self.uninit.write(val),
+            brand: self.brand,
+        }
+    }
+
+    /// Construct the value in place with its own constructor
+    #[inline(always)]
+    pub fn construct<F>(self, construct: F) -> Init<'s, T>
+    where
+        T: MemConstruct,
+        F: for<'b> ConstructFn<'b, T>,
+    {
+        // SAFETY: The reference is valid and aligned, the value is initialized by its constructor
+        unsafe {
+            crate::construct_raw(self.uninit.as_mut_ptr(), construct);
+            self.assume_init()
+        }
+    }
+
+    /// Mark the slot as initialized after it was written through [`MaybeUninit`]
+    ///
+    /// # Safety
+    ///
+    /// The value in the slot has to be fully initialized.
+    #[inline(always)]
+    pub unsafe fn assume_init(self) -> Init<'s, T> {
+        Init {
+            value: self.uninit.assume_init_mut(),
+            brand: self.brand,
+        }
+    }
+}
+
+impl<T> Deref for Slot<'_, T> {
+    type Target = MaybeUninit<T>;
+
+    fn deref(&self) -> &Self::Target {
+        self.uninit
+    }
+}
+
+impl<T> DerefMut for Slot<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.uninit
+    }
+}
+
+impl<T> Deref for Init<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.value
+    }
+}
+
+impl<T> DerefMut for Init<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.value
+    }
+}
@@ -1,21 +1,128 @@
 // Most functions here should be inlined accross crates as they are basic buildings block, likely
 // for very performance sensitive environments
 #![allow(clippy::inline_always)]
+#![no_std]
 
-// pub mod alloc;
 pub mod array;
+#[cfg(feature = "alloc")]
+pub mod dynamic;
+pub mod field;
 pub mod primitive;
+#[cfg(feature = "alloc")]
 pub mod heapconstruct;
+pub mod init;
+pub mod zeroed;
 
 mod util;
 
+#[cfg(feature = "alloc")]
 extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
 
 pub use memconstruct_macros::MemConstruct;
 
+#[cfg(feature = "alloc")]
+pub use dynamic::MissingFields;
+#[cfg(feature = "alloc")]
 pub use heapconstruct::{construct_box, HeapConstruct, HeapConstructExt};
+pub use field::FieldError;
+pub use init::{Init, Slot};
+pub use zeroed::{Zeroable, ZeroedConstruct};
 
-use core::mem::MaybeUninit;
+/// Items used by the code generated by the derive macro, not part of the public API.
+#[doc(hidden)]
+pub mod __private {
+    #[cfg(feature = "alloc")]
+    pub use alloc::vec::Vec;
+
+    pub use crate::FieldToken;
+
+    /// Implemented for the token of fields that are set, required by the generated `finish`.
+    ///
+    /// # Safety
+    ///
+    /// This may only be implemented for the token marking a field as initialized.
+    #[diagnostic::on_unimplemented(
+        message = "the field `{Self}` is not set",
+        label = "the construction can't be finished while `{Self}` is not set",
+        note = "set the field or give it a default with `#[memconstruct(default)]`"
+    )]
+    pub unsafe trait IsSet {}
+
+    unsafe impl IsSet for () {}
+
+    /// The state of a generated constructor: the pointer to its target, its brand and field tokens
+    /// and the set fields of dynamic constructors.
+    ///
+    /// Constructors are declared in the module of their item, where safe code can access their
+    /// fields. The fields of the state are private to this crate, it can only be created and its
+    /// set fields only be changed in unsafe code, so that safe code can't forge constructors or
+    /// redirect and finish them.
+    pub struct State<T, M: ?Sized, S = ()> {
+        ptr: *mut T,
+        set: S,
+        typestate: core::marker::PhantomData<M>,
+    }
+
+    impl<T, M: ?Sized, S> State<T, M, S> {
+        /// # Safety
+        ///
+        /// Only the generated code may create a state, for constructors whose state it knows.
+        #[inline(always)]
+        pub const unsafe fn new(ptr: *mut T, set: S) -> Self {
+            Self {
+                ptr,
+                set,
+                typestate: core::marker::PhantomData,
+            }
+        }
+
+        /// The pointer to the target of the constructor
+        #[inline(always)]
+        pub const fn ptr(&self) -> *mut T {
+            self.ptr
+        }
+
+        /// The set fields of a dynamic constructor
+        #[inline(always)]
+        pub const fn set(&self) -> &S {
+            &self.set
+        }
+
+        /// # Safety
+        ///
+        /// The set fields have to match the fields which are initialized.
+        #[inline(always)]
+        pub unsafe fn set_mut(&mut self) -> &mut S {
+            &mut self.set
+        }
+    }
+}
+
+use core::mem::{self, MaybeUninit};
+
+/// The typestate marker of a set field.
+///
+/// The constructors generated by the derive have a token parameter per field, which is either the
+/// `Unset_<field>` marker from the `<item>_memconstruct` module next to the item or `Set`.
+pub type Set = ();
+
+/// Implemented for the typestate markers of a field, [`Set`] and the `Unset_` markers.
+///
+/// Helper functions which are generic over the states of some fields of a constructor need this
+/// as bound on them.
+///
+/// # Safety
+///
+/// `IS_SET` may only be true for the token marking a field as initialized.
+pub unsafe trait FieldToken {
+    const IS_SET: bool;
+}
+
+unsafe impl FieldToken for () {
+    const IS_SET: bool = true;
+}
 
 /// Trait implemented for types that can be safely constructed anywhere in memory.
 ///
@@ -23,31 +130,99 @@ use core::mem::MaybeUninit;
 /// [`MemConstruct`](memconstruct_macros::MemConstruct) derive macro instead.
 /// 
 /// If you are interested in the inner mechanisms of this take a look at the crate docs.
+///
+/// Both the constructor and the finished token are branded with the lifetime `'b`. Construction
+/// functions are generic over the brand, so they can only return the finished token of the
+/// constructor they were given and not one of some other value.
+///
+/// # Safety
+///
+/// Obtaining a `ConstructorFinishedToken<'b>` from the `Constructor<'b>` must imply that the value
+/// behind the pointer passed to [`MemConstructConstructor::new`] is fully initialized. Both types
+/// have to be invariant over `'b`.
 pub unsafe trait MemConstruct {
-    type Constructor: MemConstructConstructor<Target = Self>;
-    type ConstructorFinishedToken;
-
-    #[doc(hidden)]
-    fn new_boxed_zst() -> Box<Self>
-    where
-        Self: Sized,
-    {
-        unreachable!("Only zsts should implement this function")
-    }
+    type Constructor<'b>: MemConstructConstructor<Target = Self>;
+    type ConstructorFinishedToken<'b>;
 }
 
 /// A type used to construct a heap constructable object.
 ///
 /// This trait is unsafe to implement and shouldn't be manually implemented. A safe implementation
 /// of this is generated when you derive `MemConstruct` for your type.
-////
+///
 /// # Implementation for Structs:
 /// For normal structs a `set` function is generated for every field, each of these `set` functions
-/// has to be called exactly once, this is checked via typestate.
+/// has to be called exactly once, this is checked via typestate. Fields marked with
+/// `#[memconstruct(default)]` or `#[memconstruct(default = expr)]` as well as `PhantomData` and
+/// unit fields don't have to be set, the generated `finish` function writes their defaults.
+/// Besides `set_<field>` every field can be constructed in place with `construct_<field>` if its
+/// type implements `MemConstruct`, or initialized through its memory with `init_<field>`, which
+/// hands out a [`Slot`] that has to be turned into an [`Init`]. The fallible `try_set_<field>` and
+/// `try_construct_<field>` functions stop the construction with a [`FieldError`] naming the field.
+/// With `#[memconstruct(into)]` on a field or on the whole struct the setters take anything that
+/// converts into the field, with `#[memconstruct(try_into)]` `try_set_<field>` takes anything that
+/// fallibly converts into it.
+/// Fields which weren't set show up as `Unset_<field>` in the type of the constructor, calling
+/// `finish` reports each of them as an error.
+///
+/// Structs and fields can be checked with `#[memconstruct(validate = path)]`, where `path` takes a
+/// reference to the struct or field and returns a `Result<(), E>`. The struct then needs an
+/// `#[memconstruct(error = Type)]` which every `E` converts into. `finish` runs the validators of
+/// the fields and then the one of the struct on the finished value in place and returns their
+/// error, the value is dropped in that case. Constructors of validated structs are only finished
+/// by `finish`.
+///
+/// The generated names can be changed: `#[memconstruct(rename = "name")]` renames a field in the
+/// generated functions and in errors, which gives tuple fields meaningful names.
+/// `#[memconstruct(setter_prefix = "with_")]` replaces the `set_` of the setters and
+/// `#[memconstruct(constructor = "FooBuilder")]` names the constructor type.
+///
+/// The constructor type is declared next to the struct with the same visibility. It has a token
+/// parameter per field, which is either [`Set`] or the `Unset_<field>` marker from the
+/// `<struct>_memconstruct` module that is also declared next to the struct. Functions can take and
+/// return constructors in specific states this way, like
+/// `FooMemConstructor<'b, Set, foo_memconstruct::Unset_y>`.
+/// The pointer and the state of a constructor are opaque, code in the module of the struct can't
+/// build constructors or change them. Constructors of different constructions can't be swapped
+/// either, as they are branded with different lifetimes.
+///
+/// The generated code refers to this crate as `::memconstruct`. Crates which re-export it from a
+/// facade crate set the path with `#[memconstruct(crate = "facade::memconstruct")]`.
+///
+/// Fields marked with `#[memconstruct(zeroed)]` have to be [`Zeroable`]. They are already set in
+/// the constructor of [`ZeroedConstruct`], which is used to construct values in zeroed memory,
+/// e.g. with `Box::heapconstruct_zeroed`. Outside of zeroed memory they are set like any other
+/// field.
+///
+/// # Implementation for Enums:
+/// For enums a `variant_<name>` function is generated for every variant. It writes the
+/// discriminant and returns a constructor for the fields of the variant, which is turned into the
+/// finished constructor of the enum with `finish` after all fields are set. The constructor of a
+/// variant is named after the constructor of the enum, like `FooMemConstructorBar` for the variant
+/// `Foo::Bar`, and declared next to it. Enums with fields need a primitive representation like
+/// `#[repr(u8)]` or `#[repr(C)]` to be constructed in place.
+///
+/// # Dropping constructors:
+/// A constructor owns the parts of the target it has already initialized. If it is dropped, for
+/// example because the construction panicked or failed, it drops exactly these parts. The finished
+/// constructor owns the whole value, it has to be forgotten once the value is handed out. This
+/// cleanup happens while unwinding, so it doesn't depend on panics being caught.
+///
+/// # Dynamic constructors:
+/// Structs marked with `#[memconstruct(dynamic)]` get a constructor which tracks the set fields at
+/// runtime instead of in its type. Its setters can be called in any order and any number of times,
+/// `finish` returns `MissingFields` if a field without a default was never set. Dynamic
+/// constructors require the `alloc` feature.
 ///
 /// # Implementation for ZSTs:
 /// For `ZSTs` the generated constructor has no functions and is always "ready". The construct
-/// functions will still be called for ZSTs.
+/// functions will still be called for ZSTs. Like every other value ZSTs are constructed behind a
+/// well aligned, non null pointer, which is dangling if there is no memory to construct them in.
+///
+/// # Safety
+///
+/// The constructor may only be turned into the `ConstructorFinishedToken` of its target after
+/// every part of the target has been initialized.
 pub unsafe trait MemConstructConstructor {
     type Target;
 
@@ -55,29 +230,51 @@ pub unsafe trait MemConstructConstructor {
     ///
     /// # Safety
     ///
-    /// The pointer has to be a valid, non dangling pointer. The pointer must be well
-    /// aligned and non null if not documented otherwise.
+    /// The pointer has to be non null, well aligned and valid for writes of `Target`. For zero
+    /// sized targets any non null, well aligned pointer is valid, e.g. [`NonNull::dangling`].
+    ///
+    /// [`NonNull::dangling`]: core::ptr::NonNull::dangling
     unsafe fn new(ptr: *mut Self::Target) -> Self;
 }
 
 /// Construct a value behind a pointer.
 ///
-/// The pointer has to be non null, non dangling and well well aligned if the documentation of T or
-/// T::Constructor doesn't state otherwise
+/// # Safety
+///
+/// The pointer has to be non null, well aligned and valid for writes of `T`, see
+/// [`MemConstructConstructor::new`].
 ///
 /// # Panics
 /// 
-/// This function will panic if the passed `construct` function panics. The value behind `ptr` is 
-/// then unspecified.
+/// This function will panic if the passed `construct` function panics. The parts of the value
+/// which were already initialized are dropped, the value behind `ptr` is then unspecified.
 #[inline(always)]
-pub unsafe fn construct_raw<
-    T: MemConstruct,
-    F: FnOnce(T::Constructor) -> T::ConstructorFinishedToken,
->(
+pub unsafe fn construct_raw<T: MemConstruct, F: for<'b> ConstructFn<'b, T>>(
     ptr: *mut T,
     construct: F,
 ) {
-    construct(T::Constructor::new(ptr));
+    // The finished constructor would drop the value it just constructed
+    mem::forget(construct(T::Constructor::new(ptr)));
+}
+
+/// Fallibly construct a value behind a pointer.
+///
+/// # Safety
+///
+/// The pointer has to be non null, well aligned and valid for writes of `T`, see
+/// [`MemConstructConstructor::new`].
+///
+/// # Errors
+///
+/// Returns the error of `construct`. The parts of the value which were already initialized are
+/// dropped, the value behind `ptr` is then unspecified.
+#[inline(always)]
+pub unsafe fn try_construct_raw<T: MemConstruct, E, F: for<'b> TryConstructFn<'b, T, E>>(
+    ptr: *mut T,
+    construct: F,
+) -> Result<(), E> {
+    // The finished constructor would drop the value it just constructed
+    construct(T::Constructor::new(ptr)).map(mem::forget)
 }
 
 /// Safely construct a value behind a [`MaybeUninit`]
@@ -87,13 +284,73 @@ pub unsafe fn construct_raw<
 /// This function panics if the passed `construct` function panics. The value inside the 
 /// [`MaybeUninit`] is then unspecified and shouldn't be assumed to be initialized.
 #[inline(always)]
-pub fn construct_maybe_uninit<
-    T: MemConstruct,
-    F: FnOnce(T::Constructor) -> T::ConstructorFinishedToken,
->(
+pub fn construct_maybe_uninit<T: MemConstruct, F: for<'b> ConstructFn<'b, T>>(
     uninit: &mut MaybeUninit<T>,
     construct: F,
 ) {
     unsafe { construct_raw(uninit.as_mut_ptr(), construct) }
 }
 
+/// A function which turns the constructor of a `T` into its finished token.
+///
+/// Construction functions require `for<'b> ConstructFn<'b, T>`, so the returned token has to stem
+/// from the passed constructor. This is implemented for all matching closures and functions.
+pub trait ConstructFn<'b, T: MemConstruct + ?Sized>:
+    FnOnce(T::Constructor<'b>) -> T::ConstructorFinishedToken<'b>
+{
+}
+
+impl<'b, T: MemConstruct + ?Sized, F> ConstructFn<'b, T> for F where
+    F: FnOnce(T::Constructor<'b>) -> T::ConstructorFinishedToken<'b>
+{
+}
+
+/// A [`ConstructFn`] that can be called multiple times, used to construct every element of arrays.
+pub trait ConstructFnMut<'b, T: MemConstruct + ?Sized>:
+    FnMut(T::Constructor<'b>) -> T::ConstructorFinishedToken<'b>
+{
+}
+
+impl<'b, T: MemConstruct + ?Sized, F> ConstructFnMut<'b, T> for F where
+    F: FnMut(T::Constructor<'b>) -> T::ConstructorFinishedToken<'b>
+{
+}
+
+/// A fallible [`ConstructFn`].
+pub trait TryConstructFn<'b, T: MemConstruct + ?Sized, E>:
+    FnOnce(T::Constructor<'b>) -> Result<T::ConstructorFinishedToken<'b>, E>
+{
+}
+
+impl<'b, T: MemConstruct + ?Sized, E, F> TryConstructFn<'b, T, E> for F where
+    F: FnOnce(T::Constructor<'b>) -> Result<T::ConstructorFinishedToken<'b>, E>
+{
+}
+
+/// Declare a construction function which can be stored and used later.
+///
+/// The type of the brand can't be inferred for closures which aren't directly passed to a
+/// construction function, this helper infers it for them.
+///
+/// ```
+/// # use core::mem::MaybeUninit;
+/// # use memconstruct::MemConstruct;
+/// #[derive(MemConstruct)]
+/// struct Point {
+///     x: i32,
+///     y: i32,
+/// }
+///
+/// # fn main() {
+/// let construct = memconstruct::construct_fn::<Point, _>(|c| c.set_x(1).set_y(2));
+/// let mut a = MaybeUninit::<Point>::uninit();
+/// let mut b = MaybeUninit::<Point>::uninit();
+/// memconstruct::construct_maybe_uninit(&mut a, construct);
+/// memconstruct::construct_maybe_uninit(&mut b, construct);
+/// # }
+/// ```
+#[inline(always)]
+pub fn construct_fn<T: MemConstruct, F: for<'b> ConstructFn<'b, T>>(construct: F) -> F {
+    construct
+}
+
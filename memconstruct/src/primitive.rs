@@ -1,49 +1,78 @@
-/// Memconstruct implementation on primitives
-///
-/// TODO write about implementation on primitives
+//! Memconstruct implementation on primitives
+//!
+//! TODO write about implementation on primitives
+
+use core::marker::PhantomData;
 
-use std::marker::PhantomData;
+use crate::{zeroed::ZeroedConstruct, MemConstruct, MemConstructConstructor};
 
-use crate::{MemConstruct, MemConstructConstructor};
+/// Invariant lifetime brand of the constructors
+type Brand<'b> = PhantomData<fn(&'b ()) -> &'b ()>;
 
 /// A primtive that can be constructed by using "`memset`" ([`core::ptr::write_bytes`])
+///
+/// # Safety
+///
+/// Every bit pattern has to be a valid value of the implementing type.
 pub unsafe trait MemconstructPrimitive {}
 
+macro_rules! memconstruct_primitive_impl {
+    ($($prim:tt)*) => {
+        $(
+            unsafe impl MemconstructPrimitive for $prim {}
+        )*
+    };
+}
+
 macro_rules! primitive_impl {
     ($($prim:tt)*) => {
         $(
             paste::paste! {
-                unsafe impl MemconstructPrimitive for $prim {}
-                
                 pub struct [<Primitive $prim ConstructionToken>];
 
-                pub struct [<Primitive $prim MemConstructor>] <Tok> {
+                pub struct [<Primitive $prim MemConstructor>] <'b, Tok> {
                     ptr: *mut $prim,
                     boo_scary: PhantomData<Tok>,
+                    brand: Brand<'b>,
                 }
 
                 unsafe impl MemConstruct for $prim {
-                    type Constructor = [<Primitive $prim MemConstructor>]
-                        <[<Primitive $prim ConstructionToken>]>;
-                    type ConstructorFinishedToken = [<Primitive $prim MemConstructor>] <()>;
+                    type Constructor<'b> = [<Primitive $prim MemConstructor>]
+                        <'b, [<Primitive $prim ConstructionToken>]>;
+                    type ConstructorFinishedToken<'b> = [<Primitive $prim MemConstructor>] <'b, ()>;
+                }
+
+                unsafe impl ZeroedConstruct for $prim {
+                    type ZeroedConstructor<'b> = [<Primitive $prim MemConstructor>] <'b, ()>;
+
+                    unsafe fn zeroed_constructor<'b>(
+                        ptr: *mut $prim,
+                    ) -> Self::ZeroedConstructor<'b> {
+                        [<Primitive $prim MemConstructor>] {
+                            ptr,
+                            boo_scary: PhantomData,
+                            brand: PhantomData,
+                        }
+                    }
                 }
 
-                unsafe impl MemConstructConstructor for [<Primitive $prim MemConstructor>] 
-                    <[<Primitive $prim ConstructionToken>]> 
+                unsafe impl<'b> MemConstructConstructor for [<Primitive $prim MemConstructor>] 
+                    <'b, [<Primitive $prim ConstructionToken>]> 
                 {
                     type Target = $prim;
                     
                     unsafe fn new(ptr: *mut $prim) -> Self {
                         Self {
                             ptr,
-                            boo_scary: PhantomData::default(),
+                            boo_scary: PhantomData,
+                            brand: PhantomData,
                         }
                     }
                 }
 
 
-                impl [<Primitive $prim MemConstructor>] <[<Primitive $prim ConstructionToken>]> {
-                    pub fn set(self, val: $prim) -> [<Primitive $prim MemConstructor>] <()> {
+                impl<'b> [<Primitive $prim MemConstructor>] <'b, [<Primitive $prim ConstructionToken>]> {
+                    pub fn set(self, val: $prim) -> [<Primitive $prim MemConstructor>] <'b, ()> {
                         // SAFETY: This operation is only unsafe if the rules of
                         // [`MemConstructConstructor::new`] were broken which can only be
                         // done in unsafe code.
@@ -53,7 +82,8 @@ macro_rules! primitive_impl {
 
                         [<Primitive $prim MemConstructor>] :: <()> {
                             ptr: self.ptr,
-                            boo_scary: PhantomData::default(),
+                            boo_scary: PhantomData,
+                            brand: PhantomData,
                         }
                     }
                 }
@@ -62,5 +92,8 @@ macro_rules! primitive_impl {
     };
 }
 
+// Not every bit pattern is a valid `char`, so it can only be `set`.
+memconstruct_primitive_impl! {u8 i8 u16 i16 u32 i32 u64 i64 f32 f64}
+
 primitive_impl! {u8 i8 u16 i16 u32 i32 u64 i64 char f32 f64}
 
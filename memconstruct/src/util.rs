@@ -1,18 +1,68 @@
-use core::panic::UnwindSafe;
+#[cfg(feature = "alloc")]
+use alloc::alloc::dealloc;
+#[cfg(feature = "alloc")]
+use core::{alloc::Layout, panic::UnwindSafe};
+use core::ptr;
 
-pub(crate) type UnwindError = ();
+#[cfg(feature = "alloc")]
+use crate::heapconstruct::PanicPayload;
+
+/// Catch a panic of `f`, requires the `std` feature.
+#[cfg(feature = "std")]
+#[inline(always)]
+pub(crate) fn catch_unwind<F, R>(f: F) -> Result<R, PanicPayload>
+where
+    F: FnOnce() -> R + UnwindSafe,
+{
+    std::panic::catch_unwind(f).map_err(PanicPayload::new)
+}
 
 /// no_std version of catch_unwind just ignores the unwind
+#[cfg(all(feature = "alloc", not(feature = "std")))]
 #[inline(always)]
-pub(crate) fn catch_unwind<F, R>(f: F) -> Result<R, UnwindError>
+pub(crate) fn catch_unwind<F, R>(f: F) -> Result<R, PanicPayload>
 where
     F: FnOnce() -> R + UnwindSafe,
 {
     Ok(f())
 }
 
+#[cfg(feature = "alloc")]
 #[inline(always)]
-pub(crate) fn resume_unwind<T>(_t: T) -> ! {
-    panic!("Resuming unwind")
+pub(crate) fn resume_unwind(payload: PanicPayload) -> ! {
+    payload.resume()
 }
 
+/// Frees an allocation when dropped, used to free the memory of a construction which failed or
+/// unwinds.
+#[cfg(feature = "alloc")]
+pub(crate) struct DeallocGuard {
+    pub(crate) ptr: *mut u8,
+    pub(crate) layout: Layout,
+}
+
+#[cfg(feature = "alloc")]
+impl Drop for DeallocGuard {
+    #[inline(always)]
+    fn drop(&mut self) {
+        // SAFETY: The guard is only created for memory allocated with this layout
+        unsafe { dealloc(self.ptr, self.layout) }
+    }
+}
+
+/// Drops the first `initialized` elements behind `start` when dropped, used to clean up arrays
+/// whose construction unwinds.
+pub(crate) struct DropGuard<T> {
+    pub(crate) start: *mut T,
+    pub(crate) initialized: usize,
+}
+
+impl<T> Drop for DropGuard<T> {
+    #[inline(always)]
+    fn drop(&mut self) {
+        // SAFETY: The guard is only created for initialized elements
+        unsafe {
+            ptr::drop_in_place(ptr::slice_from_raw_parts_mut(self.start, self.initialized));
+        }
+    }
+}
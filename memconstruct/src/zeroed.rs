@@ -0,0 +1,86 @@
+//! Construction of values in zeroed memory
+//!
+//! Memory that is known to be zeroed, like memory from [`alloc_zeroed`], already holds a valid
+//! value of every [`Zeroable`] type. Fields marked with `#[memconstruct(zeroed)]` start out set
+//! in the constructors of [`ZeroedConstruct`], so large zeroed fields never have to be written.
+//!
+//! [`alloc_zeroed`]: https://doc.rust-lang.org/alloc/alloc/fn.alloc_zeroed.html
+
+use core::marker::PhantomData;
+
+use crate::MemConstruct;
+
+/// Types for which the all-zero bit pattern is a valid value.
+///
+/// # Safety
+///
+/// Zeroed memory has to hold a valid value of the implementing type, which may also be dropped.
+pub unsafe trait Zeroable {}
+
+macro_rules! zeroable_impl {
+    ($($ty:tt)*) => {
+        $(
+            unsafe impl Zeroable for $ty {}
+        )*
+    };
+}
+
+zeroable_impl! {
+    u8 i8 u16 i16 u32 i32 u64 i64 u128 i128 usize isize f32 f64 bool char ()
+}
+
+unsafe impl<T: Zeroable, const N: usize> Zeroable for [T; N] {}
+
+unsafe impl<T: ?Sized> Zeroable for PhantomData<T> {}
+
+unsafe impl<T> Zeroable for *const T {}
+
+unsafe impl<T> Zeroable for *mut T {}
+
+/// Types which can be constructed in zeroed memory, skipping the parts that are valid when zeroed.
+///
+/// This is implemented by the [`MemConstruct`](memconstruct_macros::MemConstruct) derive for
+/// structs with typestate constructors, their fields marked with `#[memconstruct(zeroed)]` are
+/// already set in the `ZeroedConstructor`. Arrays of [`Zeroable`] elements and the primitives are
+/// fully constructed in zeroed memory.
+///
+/// # Safety
+///
+/// The `ZeroedConstructor<'b>` may only treat the parts of the value as initialized which are
+/// valid when zeroed. It has to be turned into the `ConstructorFinishedToken<'b>` of
+/// [`MemConstruct`] in the same way as the `Constructor<'b>`.
+pub unsafe trait ZeroedConstruct: MemConstruct {
+    type ZeroedConstructor<'b>;
+
+    /// Create the constructor of a value in zeroed memory
+    ///
+    /// # Safety
+    ///
+    /// The pointer has to fulfill the requirements of
+    /// [`MemConstructConstructor::new`](crate::MemConstructConstructor::new) and the memory
+    /// behind it has to be zeroed.
+    unsafe fn zeroed_constructor<'b>(ptr: *mut Self) -> Self::ZeroedConstructor<'b>;
+}
+
+/// A function which turns the zeroed constructor of a `T` into its finished token, see
+/// [`ConstructFn`](crate::ConstructFn).
+pub trait ZeroedConstructFn<'b, T: ZeroedConstruct + ?Sized>:
+    FnOnce(T::ZeroedConstructor<'b>) -> T::ConstructorFinishedToken<'b>
+{
+}
+
+impl<'b, T: ZeroedConstruct + ?Sized, F> ZeroedConstructFn<'b, T> for F where
+    F: FnOnce(T::ZeroedConstructor<'b>) -> T::ConstructorFinishedToken<'b>
+{
+}
+
+/// A fallible [`ZeroedConstructFn`].
+pub trait TryZeroedConstructFn<'b, T: ZeroedConstruct + ?Sized, E>:
+    FnOnce(T::ZeroedConstructor<'b>) -> Result<T::ConstructorFinishedToken<'b>, E>
+{
+}
+
+impl<'b, T: ZeroedConstruct + ?Sized, E, F> TryZeroedConstructFn<'b, T, E> for F where
+    F: FnOnce(T::ZeroedConstructor<'b>) -> Result<T::ConstructorFinishedToken<'b>, E>
+{
+}
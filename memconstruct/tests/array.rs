@@ -0,0 +1,22 @@
+#![cfg(feature = "alloc")]
+
+use memconstruct::{HeapConstructExt, MemConstruct};
+
+#[derive(MemConstruct)]
+#[repr(C)]
+struct Guarded {
+    before: u32,
+    data: [u32; 4],
+    after: u32,
+}
+
+#[test]
+fn memset_stays_inside_the_array() {
+    let guarded = Box::<Guarded>::heapconstruct(|c| {
+        c.set_before(7)
+            .set_after(9)
+            .construct_data(|c| c.memset(0xff))
+    });
+    assert_eq!(guarded.data, [u32::MAX; 4]);
+    assert_eq!((guarded.before, guarded.after), (7, 9));
+}
@@ -1,3 +1,5 @@
+#![cfg(feature = "alloc")]
+
 use memconstruct::{HeapConstructExt, MemConstruct};
 
 #[derive(MemConstruct, Debug, PartialEq)]
@@ -5,7 +7,7 @@ struct Morello;
 
 #[test]
 fn construct_boxed_zst() {
-    let f = |z| z;
+    let f = memconstruct::construct_fn::<Morello, _>(|z| z);
     let m0 = memconstruct::construct_box::<Morello, _>(f);
     println!("{:?}", m0);
     let m1 = Box::heapconstruct(f);
@@ -21,9 +23,9 @@ struct Forello {
 
 #[test]
 fn construct_boxed_struct() {
-    let construct = |c: <Forello as MemConstruct>::Constructor| {
+    let construct = memconstruct::construct_fn::<Forello, _>(|c| {
         c.set_x(10).set_hello_world(2.3).set_m([5u8; 4])
-    };
+    });
     let f0 = memconstruct::construct_box::<Forello, _>(construct);
     println!("{:?}", f0);
     let f1 = Box::heapconstruct(construct);
@@ -36,7 +38,7 @@ struct Borello(i32, f32, [u8; 4]);
 #[test]
 fn construct_boxed_tuple_struct() {
     let construct =
-        |c: <Borello as MemConstruct>::Constructor| c.set_0(42).set_1(6.9).set_2([4u8; 4]);
+        memconstruct::construct_fn::<Borello, _>(|c| c.set_0(42).set_1(6.9).set_2([4u8; 4]));
     let b0 = memconstruct::construct_box::<Borello, _>(construct);
     println!("{:?}", b0);
     let b1 = Box::heapconstruct(construct);
@@ -47,7 +49,7 @@ type Arr = [i32; 20];
 
 #[test]
 fn construct_boxed_array() {
-    let construct = |c: <Arr as MemConstruct>::Constructor| c.memconstruct_all(|c| c.set(42));
+    let construct = memconstruct::construct_fn::<Arr, _>(|c| c.memconstruct_all(|c| c.set(42)));
     let arr0 = memconstruct::construct_box::<Arr, _>(construct);
     println!("{:?}", arr0);
     let arr1 = Box::heapconstruct(construct);
@@ -73,3 +75,160 @@ fn box_heap_construct() {
     let arr = Box::<Arr>::heapconstruct(|b| b.set_all(|_| 10));
     assert_eq!(&*arr, &[10i32;20]);
 }
+
+#[derive(MemConstruct, Debug, PartialEq)]
+struct Grid<T> {
+    cells: [T; 16],
+    width: u32,
+}
+
+#[derive(MemConstruct, Debug, PartialEq)]
+struct Pair<A, B = i32>(A, B)
+where
+    A: Copy;
+
+#[test]
+fn construct_boxed_generic_struct() {
+    let grid = Box::<Grid<u8>>::heapconstruct(|g| g.set_width(4).set_cells([7; 16]));
+    assert_eq!(
+        &*grid,
+        &Grid {
+            cells: [7; 16],
+            width: 4
+        }
+    );
+    let pair = Box::<Pair<f32>>::heapconstruct(|p| p.set_0(1.5).set_1(3));
+    assert_eq!(&*pair, &Pair(1.5, 3));
+    let pair = memconstruct::construct_box::<Pair<u8, String>, _>(|p| {
+        p.set_1(String::from("memconstruct")).set_0(2)
+    });
+    assert_eq!(&*pair, &Pair(2, String::from("memconstruct")));
+}
+
+#[derive(MemConstruct, Debug, PartialEq)]
+struct Nested {
+    name: String,
+    forello: Forello,
+    grid: Grid<i32>,
+}
+
+#[test]
+fn construct_boxed_nested_struct() {
+    let nested = Box::<Nested>::heapconstruct(|n| {
+        n.construct_forello(|f| f.set_x(1).set_hello_world(0.5).construct_m(|m| m.memset(3)))
+            .set_name(String::from("nested"))
+            .construct_grid(|g| g.construct_cells(|c| c.set_all(|i| i as i32)).set_width(4))
+    });
+    assert_eq!(
+        &*nested,
+        &Nested {
+            name: String::from("nested"),
+            forello: Forello {
+                x: 1,
+                hello_world: 0.5,
+                m: [3; 4],
+            },
+            grid: Grid {
+                cells: core::array::from_fn(|i| i as i32),
+                width: 4,
+            },
+        }
+    );
+}
+
+#[derive(MemConstruct, Debug, Clone, Copy, PartialEq)]
+#[repr(C, packed)]
+struct WireHeader {
+    kind: u8,
+    len: u32,
+    checksum: u64,
+    magic: [u8; 3],
+}
+
+#[derive(MemConstruct, Debug, Clone, Copy, PartialEq)]
+#[repr(C, packed(2))]
+struct ShortPacked {
+    flag: u8,
+    value: u64,
+    pair: [u16; 2],
+}
+
+#[test]
+fn construct_boxed_packed_struct() {
+    let header = Box::<WireHeader>::heapconstruct(|h| {
+        h.set_kind(1)
+            .set_len(0xdead_beef)
+            .set_checksum(u64::MAX - 1)
+            .construct_magic(|m| m.memset(b'm'))
+    });
+    assert_eq!(
+        *header,
+        WireHeader {
+            kind: 1,
+            len: 0xdead_beef,
+            checksum: u64::MAX - 1,
+            magic: *b"mmm",
+        }
+    );
+
+    let short = Box::<ShortPacked>::heapconstruct(|s| {
+        s.set_flag(1)
+            .set_value(42)
+            .construct_pair(|p| p.set_all(|i| i as u16))
+    });
+    assert_eq!(
+        *short,
+        ShortPacked {
+            flag: 1,
+            value: 42,
+            pair: [0, 1],
+        }
+    );
+}
+
+#[derive(MemConstruct, Debug, PartialEq)]
+struct View<'a, const N: usize> {
+    refs: [&'a str; N],
+    len: usize,
+}
+
+#[derive(MemConstruct, Debug, PartialEq)]
+struct Window<'a, 'b: 'a, T: 'b, const N: usize = 2> {
+    view: View<'a, N>,
+    items: &'b [T],
+}
+
+#[derive(MemConstruct, Debug, PartialEq)]
+struct Capacity<const N: usize>;
+
+#[derive(MemConstruct, Debug, PartialEq)]
+struct Empty<const N: usize> {}
+
+#[test]
+fn construct_boxed_lifetime_and_const_generic_struct() {
+    let text = String::from("in place construction");
+    let words = text.split(' ').collect::<Vec<_>>();
+    let view = Box::<View<'_, 3>>::heapconstruct(|v| {
+        v.construct_refs(|r| r.set_all(|i| words[i])).set_len(3)
+    });
+    assert_eq!(
+        &*view,
+        &View {
+            refs: ["in", "place", "construction"],
+            len: 3
+        }
+    );
+
+    let items = [1u16, 2, 3];
+    let window = Box::<Window<'_, '_, u16>>::heapconstruct(|w| {
+        w.set_items(&items[1..])
+            .construct_view(|v| v.set_refs([words[0], words[2]]).set_len(2))
+    });
+    assert_eq!(window.items, &[2, 3]);
+    assert_eq!(window.view.refs, ["in", "construction"]);
+
+    let capacity = Box::<Capacity<16>>::heapconstruct(|c| c);
+    assert_eq!(&*capacity, &Capacity::<16>);
+    let empty = memconstruct::construct_box::<Empty<4>, _>(|e| e);
+    assert_eq!(&*empty, &Empty::<4> {});
+}
@@ -0,0 +1,12 @@
+//! Helpers shared by the integration tests
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Counts how often values were dropped in the referenced counter
+pub struct Counted(pub &'static AtomicUsize);
+
+impl Drop for Counted {
+    fn drop(&mut self) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
@@ -0,0 +1,7 @@
+#![cfg(feature = "alloc")]
+
+#[test]
+fn compile_fail() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/compile_fail/*.rs");
+}
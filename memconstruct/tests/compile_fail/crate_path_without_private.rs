@@ -0,0 +1,18 @@
+use memconstruct::MemConstruct;
+
+/// A facade without the items of the generated code, `::memconstruct` would still have them
+mod facade {
+    pub use memconstruct::{
+        construct_raw, try_construct_raw, zeroed, ConstructFn, FieldError, Init, MemConstruct,
+        MemConstructConstructor, Slot, TryConstructFn,
+    };
+}
+
+#[derive(MemConstruct)]
+#[memconstruct(crate = "facade")]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+fn main() {}
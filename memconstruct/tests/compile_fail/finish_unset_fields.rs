@@ -0,0 +1,14 @@
+use memconstruct::{HeapConstructExt, MemConstruct};
+
+#[derive(MemConstruct)]
+struct Point {
+    x: i32,
+    y: i32,
+    z: i32,
+    #[memconstruct(default)]
+    w: i32,
+}
+
+fn main() {
+    let _ = Box::<Point>::heapconstruct(|c| c.set_x(1).finish());
+}
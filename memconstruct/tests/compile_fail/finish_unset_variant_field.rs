@@ -0,0 +1,12 @@
+use memconstruct::{HeapConstructExt, MemConstruct};
+
+#[derive(MemConstruct)]
+#[repr(u8)]
+enum Shape {
+    Circle { radius: f32 },
+    Rect { width: f32, height: f32 },
+}
+
+fn main() {
+    let _ = Box::<Shape>::heapconstruct(|c| c.variant_rect().set_width(1.0).finish());
+}
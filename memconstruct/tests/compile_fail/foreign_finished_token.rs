@@ -0,0 +1,22 @@
+use std::mem::MaybeUninit;
+
+use memconstruct::{HeapConstructExt, MemConstruct};
+
+#[derive(MemConstruct)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+fn main() {
+    let _ = Box::<Point>::heapconstruct(|_c| {
+        let mut other = MaybeUninit::<Point>::uninit();
+        let mut token = None;
+        memconstruct::construct_maybe_uninit(&mut other, |c| {
+            let finished = c.set_x(1).set_y(2);
+            token = Some(finished);
+            unreachable!()
+        });
+        token.unwrap()
+    });
+}
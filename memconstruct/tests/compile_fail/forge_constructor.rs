@@ -0,0 +1,16 @@
+use memconstruct::MemConstruct;
+
+#[derive(MemConstruct)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+type Constructor = <Point as MemConstruct>::Constructor<'static>;
+
+fn main() {
+    let c = Constructor {
+        state: memconstruct::__private::State::new(std::ptr::null_mut(), ()),
+    };
+    let _ = c.set_x(1);
+}
@@ -0,0 +1,17 @@
+use memconstruct::{HeapConstructExt, MemConstruct};
+
+#[derive(MemConstruct)]
+struct Pair {
+    a: u32,
+    b: u32,
+}
+
+fn main() {
+    let _ = Box::<Pair>::heapconstruct(|c| {
+        c.init_a(|a| {
+            let _ = Box::<Pair>::heapconstruct(|other| other.init_a(|_| a.write(1)).set_b(2));
+            unreachable!()
+        })
+        .set_b(2)
+    });
+}
@@ -0,0 +1,17 @@
+#![forbid(unsafe_code)]
+
+use memconstruct::{HeapConstructExt, MemConstruct};
+
+#[derive(MemConstruct)]
+#[memconstruct(dynamic)]
+struct Config {
+    name: String,
+    retries: u32,
+}
+
+fn main() {
+    let _ = Box::<Config>::try_heapconstruct_fallible(|mut c| {
+        c.state.set = [u64::MAX; 1];
+        c.finish()
+    });
+}
@@ -0,0 +1,5 @@
+use memconstruct::HeapConstructExt;
+
+fn main() {
+    let _ = Box::<[char; 2]>::heapconstruct(|c| c.memset(0xff));
+}
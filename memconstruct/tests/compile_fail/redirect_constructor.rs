@@ -0,0 +1,17 @@
+#![forbid(unsafe_code)]
+
+use memconstruct::{HeapConstructExt, MemConstruct};
+
+#[derive(MemConstruct)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+fn main() {
+    let mut elsewhere = Point { x: 0, y: 0 };
+    let _ = Box::<Point>::heapconstruct(|mut c| {
+        c.state.ptr = &mut elsewhere;
+        c.set_x(1).set_y(2)
+    });
+}
@@ -0,0 +1,9 @@
+use memconstruct::{HeapConstructExt, MemConstruct};
+
+#[derive(MemConstruct)]
+#[memconstruct(setter_prefix = "with_", constructor = "PointBuilder")]
+struct Point(#[memconstruct(rename = "x")] i32, #[memconstruct(rename = "y")] i32);
+
+fn main() {
+    let _ = Box::<Point>::heapconstruct(|c| c.with_x(1).finish());
+}
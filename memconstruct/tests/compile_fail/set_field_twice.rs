@@ -0,0 +1,11 @@
+use memconstruct::{HeapConstructExt, MemConstruct};
+
+#[derive(MemConstruct)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+fn main() {
+    let _ = Box::<Point>::heapconstruct(|c| c.set_x(1).set_x(2).set_y(3));
+}
@@ -0,0 +1,20 @@
+use memconstruct::{HeapConstructExt, MemConstruct};
+
+fn positive(value: &i32) -> Result<(), &'static str> {
+    if *value > 0 {
+        Ok(())
+    } else {
+        Err("not positive")
+    }
+}
+
+#[derive(MemConstruct)]
+#[memconstruct(error = &'static str)]
+struct Positive {
+    #[memconstruct(validate = positive)]
+    value: i32,
+}
+
+fn main() {
+    let _ = Box::<Positive>::heapconstruct(|c| c.set_value(-1));
+}
@@ -0,0 +1,19 @@
+#![forbid(unsafe_code)]
+
+use memconstruct::{HeapConstructExt, MemConstruct};
+
+#[derive(MemConstruct)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+fn main() {
+    let _ = Box::<Point>::heapconstruct(|mut a| {
+        let _ = Box::<Point>::heapconstruct(|mut b| {
+            std::mem::swap(&mut a.state, &mut b.state);
+            b.set_x(1).set_y(2)
+        });
+        a.set_x(1).set_y(2)
+    });
+}
@@ -0,0 +1,9 @@
+use memconstruct::MemConstruct;
+
+#[derive(MemConstruct)]
+struct Register {
+    #[memconstruct(default = *(0x1000 as *const u32))]
+    value: u32,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use memconstruct::MemConstruct;
+
+#[derive(MemConstruct)]
+#[memconstruct(dynamic)]
+struct Flags {
+    #[memconstruct(default = std::mem::transmute::<u8, bool>(3))]
+    enabled: bool,
+}
+
+fn main() {}
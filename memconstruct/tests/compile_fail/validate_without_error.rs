@@ -0,0 +1,17 @@
+use memconstruct::MemConstruct;
+
+fn sorted(values: &[u8; 4]) -> Result<(), ()> {
+    if values.windows(2).all(|w| w[0] <= w[1]) {
+        Ok(())
+    } else {
+        Err(())
+    }
+}
+
+#[derive(MemConstruct)]
+struct Sorted {
+    #[memconstruct(validate = sorted)]
+    values: [u8; 4],
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+use memconstruct::{HeapConstructExt, MemConstruct};
+
+#[derive(MemConstruct)]
+struct Counter {
+    #[memconstruct(zeroed)]
+    count: u64,
+    step: u64,
+}
+
+fn main() {
+    let _ = Box::<Counter>::heapconstruct_zeroed(|c| c.set_count(1).set_step(1));
+}
@@ -0,0 +1,10 @@
+use memconstruct::MemConstruct;
+
+#[derive(MemConstruct)]
+struct Named {
+    #[memconstruct(zeroed)]
+    name: String,
+    len: usize,
+}
+
+fn main() {}
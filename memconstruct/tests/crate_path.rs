@@ -0,0 +1,61 @@
+#![cfg(feature = "alloc")]
+
+mod facade {
+    pub use memconstruct;
+    pub use memconstruct::MemConstruct;
+}
+
+use facade::memconstruct::{HeapConstructExt, Set};
+use facade::MemConstruct;
+
+#[derive(MemConstruct, Debug, PartialEq)]
+#[memconstruct(crate = "facade::memconstruct")]
+struct Point {
+    x: i32,
+    #[memconstruct(default)]
+    y: i32,
+}
+
+#[derive(MemConstruct, Debug, PartialEq)]
+#[memconstruct(crate = "facade::memconstruct", dynamic)]
+struct Name(String);
+
+#[derive(MemConstruct, Debug, PartialEq)]
+#[memconstruct(crate = "crate::facade::memconstruct")]
+#[repr(u8)]
+enum Shape {
+    Dot,
+    Line { len: u32 },
+}
+
+#[derive(MemConstruct)]
+#[memconstruct(crate = "crate::facade::memconstruct")]
+struct Unit;
+
+#[test]
+fn derive_through_a_facade() {
+    let point = Box::<Point>::heapconstruct(|c| c.set_x(1).finish());
+    assert_eq!(*point, Point { x: 1, y: 0 });
+
+    let name = Box::<Name>::heapconstruct(|c| c.set_0(String::from("a")).finish().unwrap());
+    assert_eq!(name.0, "a");
+
+    let line = Box::<Shape>::heapconstruct(|c| c.variant_line().set_len(3).finish());
+    assert_eq!(*line, Shape::Line { len: 3 });
+    let dot = Box::<Shape>::heapconstruct(|c| c.variant_dot());
+    assert_eq!(*dot, Shape::Dot);
+
+    let _ = Box::<Unit>::heapconstruct(|c| c);
+}
+
+fn set_x<'b>(
+    c: PointMemConstructor<'b, point_memconstruct::Unset_x, point_memconstruct::Unset_y>,
+) -> PointMemConstructor<'b, Set, point_memconstruct::Unset_y> {
+    c.set_x(2)
+}
+
+#[test]
+fn constructor_names_use_the_facade() {
+    let point = Box::<Point>::heapconstruct(|c| set_x(c).set_y(3).finish());
+    assert_eq!(*point, Point { x: 2, y: 3 });
+}
@@ -0,0 +1,62 @@
+#![cfg(feature = "alloc")]
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    panic,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use memconstruct::{HeapConstructExt, MemConstruct};
+
+/// Tracks the number of live allocations of the test binary
+struct CountingAlloc;
+
+static LIVE: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        LIVE.fetch_add(1, Ordering::SeqCst);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        LIVE.fetch_sub(1, Ordering::SeqCst);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOC: CountingAlloc = CountingAlloc;
+
+#[derive(MemConstruct)]
+struct Large {
+    head: [u64; 32],
+    tail: [u64; 32],
+}
+
+fn boom() -> [u64; 32] {
+    panic!("boom")
+}
+
+// The allocation counter is global, so everything is checked in a single test
+#[test]
+fn free_allocation_on_failure_and_unwind() {
+    panic::set_hook(Box::new(|_| {}));
+    let live = LIVE.load(Ordering::SeqCst);
+
+    let res = Box::<Large>::heapconstruct_fallible(|c| {
+        let _c = c.set_head([1; 32]);
+        Err(())
+    });
+    assert!(res.is_err());
+    assert_eq!(LIVE.load(Ordering::SeqCst), live);
+
+    let res = panic::catch_unwind(|| {
+        Box::<Large>::heapconstruct(|c| c.set_head([1; 32]).set_tail(boom()))
+    });
+    // The payload of the panic is allocated as well
+    drop(res);
+    assert_eq!(LIVE.load(Ordering::SeqCst), live);
+
+    let _ = panic::take_hook();
+}
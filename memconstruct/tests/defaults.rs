@@ -0,0 +1,101 @@
+#![cfg(feature = "alloc")]
+
+use std::marker::PhantomData;
+
+use memconstruct::{HeapConstructExt, MemConstruct};
+
+fn default_retries() -> u32 {
+    3
+}
+
+#[derive(MemConstruct, Debug, PartialEq)]
+struct Config<T> {
+    name: String,
+    #[memconstruct(default)]
+    verbose: bool,
+    #[memconstruct(default = default_retries())]
+    retries: u32,
+    #[memconstruct(default = [7; 8])]
+    table: [u8; 8],
+    marker: PhantomData<T>,
+    unit: (),
+}
+
+#[test]
+fn finish_fills_defaults() {
+    let config =
+        Box::<Config<i32>>::heapconstruct(|c| c.set_name(String::from("memconstruct")).finish());
+    assert_eq!(
+        *config,
+        Config {
+            name: String::from("memconstruct"),
+            verbose: false,
+            retries: 3,
+            table: [7; 8],
+            marker: PhantomData,
+            unit: (),
+        }
+    );
+}
+
+#[test]
+fn finish_keeps_set_fields() {
+    let config = Box::<Config<i32>>::heapconstruct(|c| {
+        c.set_retries(10)
+            .set_name(String::from("set"))
+            .construct_table(|t| t.set_all(|i| i as u8))
+            .set_unit(())
+            .finish()
+    });
+    assert_eq!(config.retries, 10);
+    assert_eq!(config.table, [0, 1, 2, 3, 4, 5, 6, 7]);
+    assert!(!config.verbose);
+}
+
+#[derive(MemConstruct, Debug, PartialEq)]
+struct Tagged(#[memconstruct(default = 0xff)] u8, u64, PhantomData<()>);
+
+#[test]
+fn finish_tuple_struct() {
+    let tagged = Box::<Tagged>::heapconstruct(|c| c.set_1(42).finish());
+    assert_eq!(*tagged, Tagged(0xff, 42, PhantomData));
+}
+
+#[derive(MemConstruct, Debug, PartialEq)]
+#[repr(C, packed)]
+struct PackedHeader {
+    kind: u8,
+    #[memconstruct(default = 1500)]
+    len: u32,
+}
+
+#[test]
+fn finish_packed() {
+    let header = Box::<PackedHeader>::heapconstruct(|c| c.set_kind(2).finish());
+    assert_eq!(*header, PackedHeader { kind: 2, len: 1500 });
+}
+
+#[derive(MemConstruct, Debug, PartialEq)]
+#[repr(u8)]
+enum Event {
+    Key {
+        code: u32,
+        #[memconstruct(default)]
+        repeat: bool,
+    },
+    Tick(#[memconstruct(default = 60)] u16),
+}
+
+#[test]
+fn finish_enum_variant() {
+    let key = Box::<Event>::heapconstruct(|c| c.variant_key().set_code(13).finish());
+    assert_eq!(
+        *key,
+        Event::Key {
+            code: 13,
+            repeat: false
+        }
+    );
+    let tick = Box::<Event>::heapconstruct(|c| c.variant_tick().finish());
+    assert_eq!(*tick, Event::Tick(60));
+}
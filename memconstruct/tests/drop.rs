@@ -0,0 +1,157 @@
+#![cfg(feature = "alloc")]
+
+mod common;
+
+use std::{
+    panic,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use memconstruct::{heapconstruct::HeapConstructError, HeapConstructExt, MemConstruct};
+
+use common::Counted;
+
+fn boom() -> Counted {
+    panic!("boom")
+}
+
+#[derive(MemConstruct)]
+struct Pair {
+    a: Counted,
+    b: Counted,
+}
+
+#[derive(MemConstruct)]
+struct Outer {
+    first: Counted,
+    pair: Pair,
+    last: Counted,
+}
+
+#[test]
+fn drop_set_fields_on_panic() {
+    static DROPS: AtomicUsize = AtomicUsize::new(0);
+    let res = panic::catch_unwind(|| {
+        Box::<Pair>::heapconstruct(|c| c.set_a(Counted(&DROPS)).set_b(boom()))
+    });
+    assert!(res.is_err());
+    assert_eq!(DROPS.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn drop_set_fields_on_construct_failed() {
+    static DROPS: AtomicUsize = AtomicUsize::new(0);
+    let res = Box::<Pair>::heapconstruct_fallible(|c| {
+        let c = c.set_b(Counted(&DROPS));
+        let a: Result<Counted, &str> = Err("no a");
+        Ok(c.set_a(a?))
+    });
+    assert!(matches!(res, Err(HeapConstructError::ConstructFailed("no a"))));
+    assert_eq!(DROPS.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn drop_nested_fields_on_panic() {
+    static DROPS: AtomicUsize = AtomicUsize::new(0);
+    let res = panic::catch_unwind(|| {
+        Box::<Outer>::heapconstruct(|c| {
+            c.set_first(Counted(&DROPS))
+                .construct_pair(|p| p.set_a(Counted(&DROPS)).set_b(boom()))
+                .set_last(Counted(&DROPS))
+        })
+    });
+    assert!(res.is_err());
+    assert_eq!(DROPS.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn drop_finished_value_once() {
+    static DROPS: AtomicUsize = AtomicUsize::new(0);
+    let outer = Box::<Outer>::heapconstruct(|c| {
+        c.set_first(Counted(&DROPS))
+            .construct_pair(|p| p.set_a(Counted(&DROPS)).set_b(Counted(&DROPS)))
+            .set_last(Counted(&DROPS))
+    });
+    assert_eq!(DROPS.load(Ordering::SeqCst), 0);
+    drop(outer);
+    assert_eq!(DROPS.load(Ordering::SeqCst), 4);
+}
+
+#[derive(MemConstruct)]
+#[repr(C, packed)]
+struct Packed {
+    tag: u8,
+    a: Counted,
+    b: Counted,
+}
+
+#[test]
+fn drop_packed_fields_on_construct_failed() {
+    static DROPS: AtomicUsize = AtomicUsize::new(0);
+    let res = Box::<Packed>::heapconstruct_fallible(|c| {
+        let _c = c.set_tag(1).set_a(Counted(&DROPS));
+        Err(())
+    });
+    assert!(matches!(res, Err(HeapConstructError::ConstructFailed(()))));
+    assert_eq!(DROPS.load(Ordering::SeqCst), 1);
+}
+
+#[derive(MemConstruct)]
+#[repr(u8)]
+#[allow(dead_code)]
+enum Resource {
+    Empty,
+    Pair(Counted, Counted),
+}
+
+#[test]
+fn drop_variant_fields_on_construct_failed() {
+    static DROPS: AtomicUsize = AtomicUsize::new(0);
+    let res = Box::<Resource>::heapconstruct_fallible(|c| {
+        let _c = c.variant_pair().set_1(Counted(&DROPS));
+        Err(())
+    });
+    assert!(matches!(res, Err(HeapConstructError::ConstructFailed(()))));
+    assert_eq!(DROPS.load(Ordering::SeqCst), 1);
+
+    let res = Box::<Resource>::heapconstruct_fallible(|c| {
+        let _c = c
+            .variant_pair()
+            .set_0(Counted(&DROPS))
+            .set_1(Counted(&DROPS))
+            .finish();
+        Err(())
+    });
+    assert!(matches!(res, Err(HeapConstructError::ConstructFailed(()))));
+    assert_eq!(DROPS.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn drop_array_elements_once() {
+    static DROPS: AtomicUsize = AtomicUsize::new(0);
+    let arr = Box::<[Counted; 8]>::heapconstruct(|c| c.set_all(|_| Counted(&DROPS)));
+    assert_eq!(DROPS.load(Ordering::SeqCst), 0);
+    drop(arr);
+    assert_eq!(DROPS.load(Ordering::SeqCst), 8);
+
+    let res = Box::<[Counted; 8]>::heapconstruct_fallible(|c| {
+        let _c = c.set_all(|_| Counted(&DROPS));
+        Err(())
+    });
+    assert!(matches!(res, Err(HeapConstructError::ConstructFailed(()))));
+    assert_eq!(DROPS.load(Ordering::SeqCst), 16);
+}
+
+#[test]
+fn drop_array_elements_on_unwind() {
+    static DROPS: AtomicUsize = AtomicUsize::new(0);
+    // Without the std feature the panic isn't caught by memconstruct, the elements are dropped
+    // while unwinding
+    let res = panic::catch_unwind(|| {
+        Box::<[Counted; 8]>::heapconstruct(|c| {
+            c.set_all(|i| if i == 5 { boom() } else { Counted(&DROPS) })
+        })
+    });
+    assert!(res.is_err());
+    assert_eq!(DROPS.load(Ordering::SeqCst), 5);
+}
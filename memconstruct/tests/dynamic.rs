@@ -0,0 +1,154 @@
+#![cfg(feature = "alloc")]
+
+mod common;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use memconstruct::{heapconstruct::HeapConstructError, HeapConstructExt, MemConstruct};
+
+use common::Counted;
+
+#[derive(MemConstruct)]
+#[memconstruct(dynamic)]
+struct Server {
+    name: String,
+    port: u16,
+    #[memconstruct(default = 4)]
+    workers: usize,
+    log: Counted,
+}
+
+#[test]
+fn dynamic_any_order() {
+    static DROPS: AtomicUsize = AtomicUsize::new(0);
+    let server = Box::<Server>::try_heapconstruct_fallible(|c| {
+        c.set_log(Counted(&DROPS))
+            .set_port(8080)
+            .set_name(String::from("memconstruct"))
+            .finish()
+    });
+    let Ok(server) = server else {
+        panic!("construction failed")
+    };
+    assert_eq!(server.name, "memconstruct");
+    assert_eq!(server.port, 8080);
+    assert_eq!(server.workers, 4);
+    drop(server);
+    assert_eq!(DROPS.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn dynamic_missing_fields() {
+    static DROPS: AtomicUsize = AtomicUsize::new(0);
+    let res = Box::<Server>::try_heapconstruct_fallible(|c| c.set_log(Counted(&DROPS)).finish());
+    let Err(HeapConstructError::ConstructFailed(missing)) = res else {
+        panic!("construction didn't fail")
+    };
+    assert_eq!(missing.fields(), &["name", "port"]);
+    assert_eq!(
+        missing.to_string(),
+        "construction finished with unset fields: `name`, `port`"
+    );
+    assert_eq!(DROPS.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn dynamic_set_twice() {
+    static DROPS: AtomicUsize = AtomicUsize::new(0);
+    let server = Box::<Server>::heapconstruct_fallible(|c| {
+        c.set_name(String::from("first"))
+            .set_log(Counted(&DROPS))
+            .set_log(Counted(&DROPS))
+            .set_port(1)
+            .set_name(String::from("second"))
+            .set_workers(1)
+            .finish()
+    });
+    let Ok(server) = server else {
+        panic!("construction failed")
+    };
+    assert_eq!(DROPS.load(Ordering::SeqCst), 1);
+    assert_eq!(server.name, "second");
+    assert_eq!(server.workers, 1);
+}
+
+/// Calls the setters in order on the constructor
+macro_rules! set_fields {
+    ($c:expr, $($setter:ident($val:expr))*) => {
+        $c$(.$setter($val))*
+    };
+}
+
+#[derive(MemConstruct, Debug, PartialEq)]
+#[memconstruct(dynamic)]
+struct Wide(
+    u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8,
+    u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8,
+    u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8,
+);
+
+#[test]
+fn dynamic_many_fields() {
+    let res = Box::<Wide>::try_heapconstruct_fallible(|c| {
+        set_fields!(c,
+            set_0(0) set_1(1) set_2(2) set_3(3) set_4(4) set_5(5) set_6(6) set_7(7) set_8(8)
+            set_9(9) set_10(10) set_11(11) set_12(12) set_13(13) set_14(14) set_15(15) set_16(16)
+            set_17(17) set_18(18) set_19(19) set_20(20) set_21(21) set_22(22) set_23(23) set_24(24)
+            set_25(25) set_26(26) set_27(27) set_28(28) set_29(29) set_30(30) set_31(31) set_32(32)
+            set_33(33) set_34(34) set_35(35) set_36(36) set_37(37) set_38(38) set_39(39) set_40(40)
+            set_41(41) set_42(42) set_43(43) set_44(44) set_45(45) set_46(46) set_47(47) set_48(48)
+            set_49(49) set_50(50) set_51(51) set_52(52) set_53(53) set_54(54) set_55(55) set_56(56)
+            set_57(57) set_58(58) set_59(59) set_60(60) set_61(61) set_62(62) set_63(63) set_64(64)
+            set_66(66) set_67(67) set_68(68) set_69(69)
+        )
+        .finish()
+    });
+    let Err(HeapConstructError::ConstructFailed(missing)) = res else {
+        panic!("construction didn't fail")
+    };
+    assert_eq!(missing.fields(), &["65"]);
+
+    let res = Box::<Wide>::try_heapconstruct_fallible(|c| {
+        set_fields!(c,
+            set_0(0) set_1(1) set_2(2) set_3(3) set_4(4) set_5(5) set_6(6) set_7(7) set_8(8)
+            set_9(9) set_10(10) set_11(11) set_12(12) set_13(13) set_14(14) set_15(15) set_16(16)
+            set_17(17) set_18(18) set_19(19) set_20(20) set_21(21) set_22(22) set_23(23) set_24(24)
+            set_25(25) set_26(26) set_27(27) set_28(28) set_29(29) set_30(30) set_31(31) set_32(32)
+            set_33(33) set_34(34) set_35(35) set_36(36) set_37(37) set_38(38) set_39(39) set_40(40)
+            set_41(41) set_42(42) set_43(43) set_44(44) set_45(45) set_46(46) set_47(47) set_48(48)
+            set_49(49) set_50(50) set_51(51) set_52(52) set_53(53) set_54(54) set_55(55) set_56(56)
+            set_57(57) set_58(58) set_59(59) set_60(60) set_61(61) set_62(62) set_63(63) set_64(64)
+            set_66(66) set_67(67) set_68(68) set_69(69) set_65(65)
+        )
+        .finish()
+    });
+    let Ok(wide) = res else {
+        panic!("construction failed")
+    };
+    assert_eq!((wide.0, wide.63, wide.64, wide.65, wide.69), (0, 63, 64, 65, 69));
+}
+
+#[derive(MemConstruct, Debug, PartialEq)]
+#[memconstruct(dynamic)]
+#[repr(C, packed)]
+struct Frame {
+    kind: u8,
+    payload: [u32; 4],
+}
+
+#[test]
+fn dynamic_packed() {
+    let frame = Box::<Frame>::try_heapconstruct_fallible(|c| {
+        c.set_payload([0, 1, 2, 3]).set_kind(3).finish()
+    });
+    let Ok(frame) = frame else {
+        panic!("construction failed")
+    };
+    assert_eq!(
+        *frame,
+        Frame {
+            kind: 3,
+            payload: [0, 1, 2, 3]
+        }
+    );
+}
@@ -0,0 +1,135 @@
+#![cfg(feature = "alloc")]
+
+use memconstruct::{HeapConstructExt, MemConstruct};
+
+#[derive(MemConstruct, Debug, PartialEq)]
+#[repr(u8)]
+#[allow(clippy::large_enum_variant)]
+enum Message {
+    Ping,
+    Text { id: u32, body: [u8; 8] },
+    Bulk([u8; 4096]),
+}
+
+#[test]
+fn construct_boxed_enum_variants() {
+    let ping = Box::<Message>::heapconstruct(|m| m.variant_ping());
+    assert_eq!(&*ping, &Message::Ping);
+
+    let text = Box::<Message>::heapconstruct(|m| {
+        m.variant_text().set_body(*b"memconst").set_id(7).finish()
+    });
+    assert_eq!(
+        &*text,
+        &Message::Text {
+            id: 7,
+            body: *b"memconst"
+        }
+    );
+
+    let bulk = Box::<Message>::heapconstruct(|m| m.variant_bulk().set_0([3; 4096]).finish());
+    assert_eq!(&*bulk, &Message::Bulk([3; 4096]));
+}
+
+#[derive(MemConstruct, Debug, PartialEq)]
+#[repr(C)]
+enum Shape<T> {
+    Point,
+    Circle(T),
+    Rect { width: T, height: u8 },
+}
+
+#[derive(MemConstruct, Debug, PartialEq)]
+#[repr(C, u16)]
+enum Sparse {
+    Low = 3,
+    Mid(u8, f64),
+    High = 300,
+    Highest { value: i16 },
+}
+
+#[test]
+fn construct_boxed_c_enum() {
+    let circle = Box::<Shape<f64>>::heapconstruct(|s| s.variant_circle().set_0(2.5).finish());
+    assert_eq!(&*circle, &Shape::Circle(2.5));
+    let rect = Box::<Shape<u64>>::heapconstruct(|s| {
+        s.variant_rect().set_height(3).set_width(u64::MAX).finish()
+    });
+    assert_eq!(
+        &*rect,
+        &Shape::Rect {
+            width: u64::MAX,
+            height: 3
+        }
+    );
+    let point = Box::<Shape<String>>::heapconstruct(|s| s.variant_point());
+    assert_eq!(&*point, &Shape::Point);
+
+    let mid = Box::<Sparse>::heapconstruct(|s| s.variant_mid().set_0(1).set_1(0.5).finish());
+    assert_eq!(&*mid, &Sparse::Mid(1, 0.5));
+    let highest = Box::<Sparse>::heapconstruct(|s| s.variant_highest().set_value(-2).finish());
+    assert_eq!(&*highest, &Sparse::Highest { value: -2 });
+    let high = Box::<Sparse>::heapconstruct(|s| s.variant_high());
+    assert_eq!(&*high, &Sparse::High);
+}
+
+#[derive(MemConstruct, Debug, PartialEq)]
+enum Direction {
+    North,
+    EastByNorthEast,
+}
+
+#[test]
+fn construct_boxed_fieldless_enum() {
+    let direction = Box::<Direction>::heapconstruct(|d| d.variant_east_by_north_east());
+    assert_eq!(&*direction, &Direction::EastByNorthEast);
+}
+
+#[derive(MemConstruct)]
+#[repr(u8)]
+#[allow(clippy::large_enum_variant)]
+enum Payload {
+    Empty,
+    Bulk([u8; 1 << 20]),
+}
+
+#[test]
+fn construct_large_variant_in_place() {
+    let bulk = Box::<Payload>::heapconstruct(|p| {
+        p.variant_bulk().construct_0(|b| b.memset(0xab)).finish()
+    });
+    match &*bulk {
+        Payload::Bulk(bytes) => assert!(bytes.iter().all(|&b| b == 0xab)),
+        Payload::Empty => panic!("Constructed the wrong variant"),
+    }
+}
+
+#[derive(MemConstruct, Debug, PartialEq)]
+#[repr(u8)]
+enum Token<'a, const N: usize> {
+    Word(&'a str),
+    Chunk { bytes: [u8; N], source: &'a [u8] },
+    End,
+}
+
+#[test]
+fn construct_enum_with_lifetime_and_const_generic() {
+    let source = String::from("word");
+    let word = Box::<Token<'_, 4>>::heapconstruct(|t| t.variant_word().set_0(&source).finish());
+    assert_eq!(*word, Token::Word("word"));
+    let chunk = Box::<Token<'_, 4>>::heapconstruct(|t| {
+        t.variant_chunk()
+            .set_source(source.as_bytes())
+            .construct_bytes(|b| b.memset(1))
+            .finish()
+    });
+    assert_eq!(
+        *chunk,
+        Token::Chunk {
+            bytes: [1; 4],
+            source: b"word"
+        }
+    );
+    let end = Box::<Token<'_, 4>>::heapconstruct(|t| t.variant_end());
+    assert_eq!(*end, Token::End);
+}
@@ -0,0 +1,111 @@
+#![cfg(feature = "std")]
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    error::Error,
+    mem, ptr,
+};
+
+use memconstruct::{
+    heapconstruct::HeapConstructError, HeapConstruct, HeapConstructExt, MemConstruct,
+    MissingFields,
+};
+
+/// Fails allocations of [`FAILING_SIZE`] bytes or more, independent of overcommit
+struct FailingAlloc;
+
+const FAILING_SIZE: usize = 1 << 40;
+
+unsafe impl GlobalAlloc for FailingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if layout.size() >= FAILING_SIZE {
+            ptr::null_mut()
+        } else {
+            System.alloc(layout)
+        }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOC: FailingAlloc = FailingAlloc;
+
+#[derive(MemConstruct, Debug, PartialEq)]
+#[memconstruct(dynamic)]
+struct Settings {
+    name: &'static str,
+    retries: u32,
+}
+
+#[test]
+fn unwrap_try_heapconstruct() {
+    let settings = Box::<[u32; 4]>::try_heapconstruct(|c| c.set_all(|i| i as u32)).unwrap();
+    assert_eq!(*settings, [0, 1, 2, 3]);
+}
+
+#[test]
+fn construct_failed_source() {
+    let err = Box::<Settings>::try_heapconstruct_fallible(|c| c.set_name("db").finish())
+        .unwrap_err();
+    assert_eq!(err.to_string(), "the construction failed");
+    assert_eq!(
+        err.source().unwrap().to_string(),
+        "construction finished with unset fields: `retries`"
+    );
+
+    let boxed: Box<dyn Error> = Box::new(err);
+    assert!(boxed.source().unwrap().is::<MissingFields>());
+}
+
+#[test]
+fn map_err_and_into_inner() {
+    let err = Box::<Settings>::try_heapconstruct_fallible(|c| c.finish()).unwrap_err();
+    let fields = err.map_err(|missing| missing.fields().len()).into_inner();
+    assert_eq!(fields, Some(2));
+
+    let err = Box::<[u8; 1 << 46]>::try_heapconstruct(|c| c.memset(0)).unwrap_err();
+    assert!(err.map_err(|never| match never {}).into_inner().is_none());
+}
+
+#[test]
+fn allocation_failure_layout() {
+    let err = Box::<[u8; 1 << 46]>::try_heapconstruct(|c| c.memset(0)).unwrap_err();
+    let HeapConstructError::AllocationFailure { layout, type_name } = err else {
+        panic!("the allocation didn't fail: {:?}", err)
+    };
+    assert_eq!(layout.size(), 1 << 46);
+    assert_eq!(layout.align(), mem::align_of::<u8>());
+    assert_eq!(type_name, "[u8; 70368744177664]");
+    assert_eq!(
+        err.to_string(),
+        "allocating 70368744177664 bytes with alignment 1 for `[u8; 70368744177664]` failed"
+    );
+}
+
+/// A heap type reporting a layout overflow, like one with a runtime length would for huge lengths
+struct Overflowing;
+
+impl HeapConstruct<u64> for Overflowing {
+    unsafe fn try_heapconstruct_fallible_raw<E, F: FnOnce(*mut u64) -> Result<(), E>>(
+        _construct: F,
+    ) -> Result<Self, HeapConstructError<E>> {
+        Err(HeapConstructError::LayoutOverflow { type_name: "u64" })
+    }
+}
+
+#[test]
+fn layout_overflow() {
+    let Err(err) = Overflowing::try_heapconstruct(|c| c.set(1)) else {
+        panic!("the layout didn't overflow")
+    };
+    assert_eq!(err.to_string(), "the layout of `u64` overflows");
+}
+
+#[test]
+#[should_panic(expected = "the layout of `u64` overflows")]
+fn heapconstruct_panics_on_layout_overflow() {
+    Overflowing::heapconstruct(|c| c.set(1));
+}
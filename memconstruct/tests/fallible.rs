@@ -0,0 +1,165 @@
+#![cfg(feature = "alloc")]
+
+mod common;
+
+use std::{
+    num::ParseIntError,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use memconstruct::{heapconstruct::HeapConstructError, FieldError, HeapConstructExt, MemConstruct};
+
+use common::Counted;
+
+#[derive(MemConstruct, Debug, PartialEq)]
+struct Limits {
+    connections: u32,
+    timeout: u64,
+}
+
+#[derive(MemConstruct, Debug, PartialEq)]
+struct Config {
+    name: String,
+    port: u16,
+    limits: Limits,
+}
+
+fn parse_config(
+    name: &str,
+    port: &str,
+    connections: &str,
+    timeout: &str,
+) -> Result<Box<Config>, HeapConstructError<FieldError<ParseIntError>>> {
+    Box::<Config>::try_heapconstruct_fallible(|c| {
+        c.set_name(name.to_owned())
+            .try_set_port(port.parse())?
+            .try_construct_limits(|c| {
+                c.try_set_connections(connections.parse())?
+                    .try_set_timeout(timeout.parse())
+            })
+            // Report the outer field with the error of the inner one
+            .map_err(|e| FieldError::new(e.field(), e.into_error().into_error()))
+    })
+}
+
+#[test]
+fn try_set_fields() {
+    let config = parse_config("proxy", "8080", "64", "30").unwrap();
+    assert_eq!(
+        *config,
+        Config {
+            name: String::from("proxy"),
+            port: 8080,
+            limits: Limits {
+                connections: 64,
+                timeout: 30
+            },
+        }
+    );
+}
+
+#[test]
+fn try_set_reports_field() {
+    let err = parse_config("proxy", "80 80", "64", "30").unwrap_err();
+    let err = err.into_inner().unwrap();
+    assert_eq!(err.field(), "port");
+    assert_eq!(err.to_string(), "setting the field `port` failed");
+    assert_eq!(err.error(), &"80 80".parse::<u16>().unwrap_err());
+
+    let err = parse_config("proxy", "8080", "64", "-1").unwrap_err();
+    assert_eq!(err.into_inner().unwrap().field(), "limits");
+}
+
+#[derive(MemConstruct)]
+struct Resources {
+    first: Counted,
+    second: Counted,
+    third: Counted,
+}
+
+#[test]
+fn try_set_drops_set_fields() {
+    static DROPS: AtomicUsize = AtomicUsize::new(0);
+    let res: Result<_, HeapConstructError<FieldError<&str>>> =
+        Box::<Resources>::try_heapconstruct_fallible(|c| {
+            c.set_first(Counted(&DROPS))
+                .set_second(Counted(&DROPS))
+                .try_set_third(Err("closed"))
+        });
+    let Err(HeapConstructError::ConstructFailed(err)) = res else {
+        panic!("the construction didn't fail")
+    };
+    assert_eq!(err, FieldError::new("third", "closed"));
+    assert_eq!(DROPS.load(Ordering::SeqCst), 2);
+}
+
+#[derive(MemConstruct)]
+struct Pair {
+    a: Counted,
+    b: Counted,
+}
+
+#[derive(MemConstruct)]
+struct Nested {
+    head: Counted,
+    pair: Pair,
+}
+
+#[test]
+fn try_construct_drops_set_fields() {
+    static DROPS: AtomicUsize = AtomicUsize::new(0);
+    let res = Box::<Nested>::try_heapconstruct_fallible(|c| {
+        c.set_head(Counted(&DROPS)).try_construct_pair(|c| {
+            let _c = c.set_a(Counted(&DROPS));
+            Err(())
+        })
+    });
+    assert_eq!(res.err().unwrap().into_inner(), Some(FieldError::new("pair", ())));
+    assert_eq!(DROPS.load(Ordering::SeqCst), 2);
+}
+
+#[derive(MemConstruct)]
+#[memconstruct(dynamic)]
+struct Dynamic(Counted, Counted);
+
+#[test]
+fn dynamic_try_set() {
+    static DROPS: AtomicUsize = AtomicUsize::new(0);
+    let res = Box::<Dynamic>::try_heapconstruct_fallible(|c| {
+        c.set_0(Counted(&DROPS))
+            .try_set_1(Err("gone"))
+            .map_err(|e| e.field())?
+            .finish()
+            .map_err(|_| "missing")
+    });
+    assert_eq!(res.err().unwrap().into_inner(), Some("1"));
+    assert_eq!(DROPS.load(Ordering::SeqCst), 1);
+}
+
+#[derive(MemConstruct, Debug, PartialEq)]
+#[repr(u8)]
+enum Value {
+    Int(i64),
+    Pair { left: u8, right: u8 },
+}
+
+#[test]
+fn enum_try_set() {
+    let value = Box::<Value>::try_heapconstruct_fallible(|c| {
+        Ok::<_, FieldError<ParseIntError>>(
+            c.variant_pair()
+                .try_set_left("3".parse())?
+                .try_set_right("4".parse())?
+                .finish(),
+        )
+    })
+    .unwrap();
+    assert_eq!(*value, Value::Pair { left: 3, right: 4 });
+
+    let err: HeapConstructError<FieldError<ParseIntError>> =
+        Box::<Value>::try_heapconstruct_fallible(|c| {
+            Ok(c.variant_int().try_set_0("x".parse())?.finish())
+        })
+        .unwrap_err();
+    assert_eq!(err.into_inner().unwrap().field(), "0");
+}
@@ -0,0 +1,81 @@
+#![cfg(feature = "alloc")]
+
+use memconstruct::{HeapConstructExt, MemConstruct};
+
+mod shapes {
+    pub mod units {
+        #[derive(Debug, PartialEq)]
+        pub struct Meters(pub f64);
+    }
+
+    use std::collections::BTreeMap as Map;
+
+    use memconstruct::MemConstruct;
+
+    use self::units::Meters;
+
+    #[derive(MemConstruct)]
+    pub struct Circle {
+        pub radius: Meters,
+        pub tags: Map<&'static str, u32>,
+    }
+
+    #[derive(MemConstruct)]
+    #[repr(u8)]
+    pub enum Shape {
+        Circle(Meters),
+        Square { side: Meters },
+    }
+}
+
+#[test]
+fn fn_local_struct() {
+    #[derive(MemConstruct)]
+    struct Local {
+        a: u32,
+        b: Inner,
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Inner(&'static str);
+
+    let local = Box::<Local>::heapconstruct(|c| c.set_a(3).set_b(Inner("local")));
+    assert_eq!(local.a, 3);
+    assert_eq!(local.b, Inner("local"));
+}
+
+#[test]
+fn fn_local_items_with_the_same_name() {
+    let first = {
+        #[derive(MemConstruct)]
+        struct Value(u8);
+
+        Box::<Value>::heapconstruct(|c| c.set_0(1)).0
+    };
+    let second = {
+        #[derive(MemConstruct)]
+        #[memconstruct(dynamic)]
+        struct Value(u16);
+
+        Box::<Value>::heapconstruct(|c| c.set_0(2).finish().unwrap()).0
+    };
+    assert_eq!((first, second), (1, 2));
+}
+
+#[test]
+fn use_imported_field_types() {
+    use shapes::units::Meters;
+
+    let circle = Box::<shapes::Circle>::heapconstruct(|c| {
+        c.set_radius(Meters(2.0)).set_tags([("round", 1)].into_iter().collect())
+    });
+    assert_eq!(circle.radius, Meters(2.0));
+    assert_eq!(circle.tags["round"], 1);
+
+    let shape =
+        Box::<shapes::Shape>::heapconstruct(|c| c.variant_square().set_side(Meters(1.0)).finish());
+    assert!(matches!(*shape, shapes::Shape::Square { side: Meters(side) } if side == 1.0));
+    let shape =
+        Box::<shapes::Shape>::heapconstruct(|c| c.variant_circle().set_0(Meters(3.0)).finish());
+    assert!(matches!(*shape, shapes::Shape::Circle(Meters(radius)) if radius == 3.0));
+}
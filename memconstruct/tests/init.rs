@@ -0,0 +1,92 @@
+#![cfg(feature = "alloc")]
+
+use std::io::{Cursor, Read};
+
+use memconstruct::{HeapConstructExt, MemConstruct};
+
+#[derive(MemConstruct, Debug, PartialEq)]
+struct Packet {
+    id: u32,
+    data: [u8; 4096],
+    checksum: u64,
+}
+
+#[test]
+fn init_field_through_slot() {
+    let packet = Box::<Packet>::heapconstruct(|c| {
+        c.init_id(|slot| slot.write(7))
+            .init_data(|slot| slot.construct(|d| d.memset(1)))
+            .set_checksum(0)
+    });
+    assert_eq!(packet.id, 7);
+    assert_eq!(packet.data, [1; 4096]);
+}
+
+#[test]
+fn init_field_with_reader() {
+    let bytes = (0..4096).map(|i| i as u8).collect::<Vec<_>>();
+    let mut reader = Cursor::new(bytes.clone());
+    let packet = Box::<Packet>::heapconstruct(|c| {
+        c.set_id(1)
+            .init_data(|slot| {
+                let mut data = slot.construct(|d| d.memset(0));
+                reader.read_exact(&mut data[..]).unwrap();
+                data
+            })
+            .set_checksum(2)
+    });
+    assert_eq!(&packet.data[..], &bytes[..]);
+}
+
+#[test]
+fn init_field_through_maybe_uninit() {
+    let packet = Box::<Packet>::heapconstruct(|c| {
+        c.init_checksum(|mut slot| {
+            // SAFETY: The slot is written through the pointer of the `MaybeUninit`
+            unsafe {
+                slot.as_mut_ptr().write(0xdead);
+                slot.assume_init()
+            }
+        })
+        .set_id(3)
+        .init_data(|slot| {
+            let mut data = slot.write([0; 4096]);
+            data[4095] = 9;
+            data
+        })
+    });
+    assert_eq!(packet.checksum, 0xdead);
+    assert_eq!(packet.data[4095], 9);
+}
+
+#[test]
+fn set_with_pointer_advances_constructor() {
+    let packet = Box::<Packet>::heapconstruct(|c| {
+        // SAFETY: The pointer is fully written
+        let c = unsafe { c.set_id_with_pointer(|ptr| ptr.write(11)) };
+        c.set_checksum(12).construct_data(|d| d.memset(13))
+    });
+    assert_eq!((packet.id, packet.checksum, packet.data[0]), (11, 12, 13));
+}
+
+#[derive(MemConstruct)]
+#[memconstruct(dynamic)]
+struct Record {
+    name: String,
+    data: [u8; 64],
+}
+
+#[test]
+fn init_dynamic_field() {
+    let record = Box::<Record>::try_heapconstruct_fallible(|c| {
+        c.init_data(|slot| slot.write([1; 64]))
+            .init_name(|slot| slot.write(String::from("first")))
+            .init_name(|slot| slot.write(String::from("second")))
+            .finish()
+    });
+    let Ok(record) = record else {
+        panic!("construction failed")
+    };
+    assert_eq!(record.name, "second");
+    assert_eq!(record.data, [1; 64]);
+}
@@ -0,0 +1,100 @@
+#![cfg(feature = "alloc")]
+
+use std::num::TryFromIntError;
+
+use memconstruct::{heapconstruct::HeapConstructError, FieldError, HeapConstructExt, MemConstruct};
+
+#[derive(MemConstruct, Debug, PartialEq)]
+struct User {
+    #[memconstruct(into)]
+    name: String,
+    #[memconstruct(into)]
+    bio: Box<str>,
+    #[memconstruct(try_into)]
+    age: u8,
+    #[memconstruct(into, try_into)]
+    score: i64,
+}
+
+#[test]
+fn into_setters() {
+    let user = Box::<User>::try_heapconstruct_fallible(|c| {
+        Ok::<_, FieldError<TryFromIntError>>(
+            c.set_name("ferris")
+                .set_bio("crab")
+                .try_set_age(9u64)?
+                .set_score(7i32)
+                .finish(),
+        )
+    })
+    .unwrap();
+    assert_eq!(
+        *user,
+        User {
+            name: String::from("ferris"),
+            bio: Box::from("crab"),
+            age: 9,
+            score: 7,
+        }
+    );
+}
+
+#[test]
+fn try_into_reports_field() {
+    let res = Box::<User>::try_heapconstruct_fallible(|c| {
+        Ok::<_, FieldError<TryFromIntError>>(
+            c.set_name("ferris")
+                .set_bio("crab")
+                .try_set_age(300u32)?
+                .try_set_score(u64::MAX)?
+                .finish(),
+        )
+    });
+    let Err(HeapConstructError::ConstructFailed(err)) = res else {
+        panic!("the conversion didn't fail")
+    };
+    assert_eq!(err.field(), "age");
+    assert_eq!(err.into_error(), u8::try_from(300u32).unwrap_err());
+}
+
+#[derive(MemConstruct, Debug, PartialEq)]
+#[memconstruct(into)]
+struct Labels(String, String, #[memconstruct(default)] Vec<String>);
+
+#[test]
+fn struct_wide_into() {
+    let labels = Box::<Labels>::heapconstruct(|c| {
+        c.set_0("left").set_1(String::from("right")).set_2(["up"].map(String::from)).finish()
+    });
+    assert_eq!(
+        *labels,
+        Labels(String::from("left"), String::from("right"), vec![String::from("up")])
+    );
+}
+
+#[derive(MemConstruct, Debug, PartialEq)]
+#[memconstruct(dynamic, into, try_into)]
+struct Limits {
+    max: u16,
+    #[memconstruct(default)]
+    path: String,
+}
+
+#[test]
+fn dynamic_into() {
+    let limits = Box::<Limits>::try_heapconstruct_fallible(|c| {
+        c.set_path("/tmp")
+            .try_set_max(1024i64)
+            .map_err(|e| e.field())?
+            .finish()
+            .map_err(|_| "missing")
+    })
+    .unwrap();
+    assert_eq!(*limits, Limits { max: 1024, path: String::from("/tmp") });
+
+    let err = Box::<Limits>::try_heapconstruct_fallible(|c| {
+        c.try_set_max(-1i64).map_err(|e| e.field())?.finish().map_err(|_| "missing")
+    })
+    .unwrap_err();
+    assert_eq!(err.into_inner(), Some("max"));
+}
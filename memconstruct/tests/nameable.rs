@@ -0,0 +1,103 @@
+#![cfg(feature = "alloc")]
+
+use memconstruct::{FieldToken, HeapConstructExt, Set};
+
+mod packet {
+    use memconstruct::MemConstruct;
+
+    #[derive(MemConstruct)]
+    pub struct Packet {
+        pub kind: u8,
+        pub len: u16,
+        pub payload: [u8; 16],
+    }
+
+    #[derive(MemConstruct, Debug, PartialEq)]
+    #[repr(u8)]
+    pub enum Message {
+        Ping,
+        Data { id: u32, body: Vec<u8> },
+    }
+
+    /// Its constructor has the name the constructor of `Message::Data` would have when it was
+    /// named after the enum and the variant
+    #[derive(MemConstruct)]
+    pub struct MessageData {
+        pub id: u32,
+    }
+
+    #[derive(MemConstruct)]
+    #[memconstruct(dynamic)]
+    pub struct Config {
+        pub name: String,
+    }
+}
+
+use packet::{
+    message_data_memconstruct, message_memconstruct, packet_memconstruct, Config,
+    ConfigMemConstructor, Message, MessageData, MessageDataMemConstructor, MessageMemConstructor,
+    MessageMemConstructorData, Packet, PacketMemConstructor,
+};
+
+type Unset = packet_memconstruct::Unset_payload;
+
+/// Fills the header of any packet, the payload is left to the caller
+fn fill_header<'b, T>(
+    c: PacketMemConstructor<'b, packet_memconstruct::Unset_kind, packet_memconstruct::Unset_len, T>,
+    kind: u8,
+) -> PacketMemConstructor<'b, Set, Set, T>
+where
+    T: FieldToken,
+{
+    c.set_kind(kind).set_len(16)
+}
+
+fn fill_payload<'b>(
+    c: PacketMemConstructor<'b, Set, Set, Unset>,
+) -> PacketMemConstructor<'b, Set, Set, Set> {
+    c.set_payload([7; 16])
+}
+
+fn data<'b>(
+    c: MessageMemConstructor<'b, message_memconstruct::Unset_variant>,
+) -> MessageMemConstructorData<'b, message_memconstruct::Unset_Data_id, Set> {
+    c.variant_data().set_body(vec![1, 2])
+}
+
+fn message_data<'b>(
+    c: MessageDataMemConstructor<'b, message_data_memconstruct::Unset_id>,
+) -> MessageDataMemConstructor<'b, Set> {
+    c.set_id(4)
+}
+
+#[test]
+fn helpers_name_intermediate_states() {
+    let packet = Box::<Packet>::heapconstruct(|c| fill_payload(fill_header(c, 3)));
+    assert_eq!((packet.kind, packet.len, packet.payload), (3, 16, [7; 16]));
+
+    let message = Box::<Message>::heapconstruct(|c| data(c).set_id(9).finish());
+    assert_eq!(
+        *message,
+        Message::Data {
+            id: 9,
+            body: vec![1, 2]
+        }
+    );
+    let ping = Box::<Message>::heapconstruct(|c| c.variant_ping());
+    assert_eq!(*ping, Message::Ping);
+
+    let message_data = Box::<MessageData>::heapconstruct(message_data);
+    assert_eq!(message_data.id, 4);
+}
+
+#[test]
+fn dynamic_constructor_is_nameable() {
+    fn name<'b>(
+        c: ConfigMemConstructor<'b, packet::config_memconstruct::Unset_fields>,
+    ) -> ConfigMemConstructor<'b, Set> {
+        c.set_name(String::from("config")).finish().unwrap()
+    }
+
+    let config = Box::<Config>::heapconstruct(name);
+    assert_eq!(config.name, "config");
+}
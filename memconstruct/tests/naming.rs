@@ -0,0 +1,93 @@
+#![cfg(feature = "alloc")]
+
+use memconstruct::{heapconstruct::HeapConstructError, FieldError, HeapConstructExt, MemConstruct};
+
+#[derive(MemConstruct, Debug, PartialEq)]
+struct Rgb(
+    #[memconstruct(rename = "red")] u8,
+    #[memconstruct(rename = "green")] u8,
+    #[memconstruct(rename = "blue")] u8,
+);
+
+#[test]
+fn rename_tuple_fields() {
+    let color = Box::<Rgb>::heapconstruct(|c| c.set_red(255).set_green(128).set_blue(0));
+    assert_eq!(*color, Rgb(255, 128, 0));
+}
+
+#[derive(MemConstruct, Debug, PartialEq)]
+#[memconstruct(setter_prefix = "with_", constructor = "RequestBuilder")]
+struct Request {
+    method: &'static str,
+    #[memconstruct(rename = "uri")]
+    path: String,
+    #[memconstruct(default)]
+    retries: u8,
+}
+
+fn get(path: &str) -> Box<Request> {
+    Box::<Request>::heapconstruct(|c| c.with_method("GET").with_uri(path.to_owned()).finish())
+}
+
+#[test]
+fn setter_prefix_and_constructor() {
+    assert_eq!(
+        *get("/index.html"),
+        Request {
+            method: "GET",
+            path: String::from("/index.html"),
+            retries: 0,
+        }
+    );
+
+    let res: Result<_, HeapConstructError<FieldError<&str>>> =
+        Box::<Request>::try_heapconstruct_fallible(|c| {
+            Ok(c
+                .with_method("POST")
+                .try_with_uri(Err("no path"))?
+                .with_retries(3)
+                .finish())
+        });
+    let Err(HeapConstructError::ConstructFailed(err)) = res else {
+        panic!("the construction didn't fail")
+    };
+    assert_eq!(err.field(), "uri");
+}
+
+#[derive(MemConstruct, Debug, PartialEq)]
+#[memconstruct(dynamic, setter_prefix = "")]
+struct Flags {
+    #[memconstruct(rename = "is_verbose")]
+    verbose: bool,
+    #[memconstruct(default)]
+    level: u8,
+}
+
+#[test]
+fn dynamic_names() {
+    let flags = Box::<Flags>::try_heapconstruct_fallible(|c| c.level(2).finish()).unwrap_err();
+    assert_eq!(flags.into_inner().unwrap().fields(), ["is_verbose"]);
+
+    let flags =
+        Box::<Flags>::try_heapconstruct_fallible(|c| c.is_verbose(true).finish()).unwrap();
+    assert_eq!(*flags, Flags { verbose: true, level: 0 });
+}
+
+#[derive(MemConstruct, Debug, PartialEq)]
+#[repr(u8)]
+#[memconstruct(setter_prefix = "with_", constructor = "ShapeBuilder")]
+enum Shape {
+    Circle(#[memconstruct(rename = "radius")] f32),
+    Rect { width: f32, height: f32 },
+}
+
+#[test]
+fn enum_names() {
+    let circle = Box::<Shape>::heapconstruct(|c| c.variant_circle().with_radius(1.5).finish());
+    assert_eq!(*circle, Shape::Circle(1.5));
+
+    let rect = Box::<Shape>::heapconstruct(|c| {
+        c.variant_rect().with_width(2.0).with_height(3.0).finish()
+    });
+    assert_eq!(*rect, Shape::Rect { width: 2.0, height: 3.0 });
+}
@@ -0,0 +1,46 @@
+//! Construction without the `alloc` feature, as it is available on bare-metal targets
+
+use core::mem::MaybeUninit;
+
+use memconstruct::MemConstruct;
+
+#[derive(MemConstruct, Debug, PartialEq)]
+struct Registers {
+    control: u32,
+    status: u8,
+    buffer: [u8; 16],
+    #[memconstruct(default)]
+    scratch: [u16; 4],
+}
+
+#[test]
+fn construct_maybe_uninit() {
+    let mut uninit = MaybeUninit::<Registers>::uninit();
+    memconstruct::construct_maybe_uninit(&mut uninit, |c| {
+        c.construct_control(|c| c.set(0x8000_0001))
+            .set_status(3)
+            .construct_buffer(|c| c.memset(0xAA))
+            .finish()
+    });
+    let registers = unsafe { uninit.assume_init() };
+    assert_eq!(
+        registers,
+        Registers {
+            control: 0x8000_0001,
+            status: 3,
+            buffer: [0xAA; 16],
+            scratch: [0; 4],
+        }
+    );
+}
+
+#[test]
+fn construct_raw_array() {
+    let mut arr = MaybeUninit::<[[u16; 4]; 3]>::uninit();
+    unsafe {
+        memconstruct::construct_raw(arr.as_mut_ptr(), |c| {
+            c.memconstruct_all(|c| c.set_all(|i| i as u16 * 2))
+        });
+    }
+    assert_eq!(unsafe { arr.assume_init() }, [[0, 2, 4, 6]; 3]);
+}
@@ -0,0 +1,108 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use std::{
+    panic,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use memconstruct::{heapconstruct::HeapConstructError, HeapConstructExt, MemConstruct};
+
+use common::Counted;
+
+#[derive(MemConstruct)]
+struct Pair {
+    a: Counted,
+    b: Counted,
+}
+
+#[test]
+fn construct_panicked_carries_payload() {
+    static DROPS: AtomicUsize = AtomicUsize::new(0);
+    let res = Box::<Pair>::try_heapconstruct(|c| {
+        let c = c.set_a(Counted(&DROPS));
+        if DROPS.load(Ordering::SeqCst) == 0 {
+            panic!("boom");
+        }
+        c.set_b(Counted(&DROPS))
+    });
+    let Err(HeapConstructError::ConstructPanicked(payload)) = res else {
+        panic!("the panic wasn't caught")
+    };
+    assert_eq!(payload.get().downcast_ref::<&str>(), Some(&"boom"));
+    assert_eq!(format!("{:?}", payload), r#"PanicPayload("boom", ..)"#);
+    assert_eq!(DROPS.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn heapconstruct_resumes_original_panic() {
+    let res = panic::catch_unwind(|| {
+        Box::<[u32; 16]>::heapconstruct(|c| {
+            c.set_all(|i| {
+                if i == 7 {
+                    panic!("original panic at {}", i);
+                }
+                i as u32
+            })
+        })
+    });
+    let payload = res.err().unwrap();
+    assert_eq!(
+        payload.downcast_ref::<String>().map(String::as_str),
+        Some("original panic at 7")
+    );
+}
+
+#[test]
+fn array_drops_constructed_elements_on_panic() {
+    static DROPS: AtomicUsize = AtomicUsize::new(0);
+    let res = Box::<[Counted; 8]>::try_heapconstruct(|c| {
+        c.set_all(|i| {
+            if i == 3 {
+                panic!("boom");
+            }
+            Counted(&DROPS)
+        })
+    });
+    let Err(HeapConstructError::ConstructPanicked(payload)) = res else {
+        panic!("the panic wasn't caught")
+    };
+    assert!(payload.into_inner().is::<&str>());
+    assert_eq!(DROPS.load(Ordering::SeqCst), 3);
+}
+
+/// Panics on the first drop of any of its values
+struct PanicOnFirstDrop(&'static AtomicUsize);
+
+impl Drop for PanicOnFirstDrop {
+    fn drop(&mut self) {
+        if self.0.fetch_add(1, Ordering::SeqCst) == 0 {
+            panic!("drop panicked");
+        }
+    }
+}
+
+#[derive(MemConstruct)]
+#[memconstruct(dynamic)]
+struct Replaced {
+    a: PanicOnFirstDrop,
+    b: u32,
+}
+
+#[test]
+fn dynamic_replaced_value_panicking_in_drop_is_dropped_once() {
+    static DROPS: AtomicUsize = AtomicUsize::new(0);
+    let res = Box::<Replaced>::try_heapconstruct_fallible(|c| {
+        c.set_a(PanicOnFirstDrop(&DROPS))
+            .set_a(PanicOnFirstDrop(&DROPS))
+            .set_b(1)
+            .finish()
+    });
+    let Err(HeapConstructError::ConstructPanicked(payload)) = res else {
+        panic!("the panic wasn't caught")
+    };
+    assert!(payload.into_inner().is::<&str>());
+    // The replaced value and the new value which wasn't written yet
+    assert_eq!(DROPS.load(Ordering::SeqCst), 2);
+}
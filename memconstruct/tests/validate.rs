@@ -0,0 +1,123 @@
+#![cfg(feature = "alloc")]
+
+mod common;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use memconstruct::{heapconstruct::HeapConstructError, HeapConstructExt, MemConstruct};
+
+use common::Counted;
+
+#[derive(Debug, PartialEq)]
+enum TableError {
+    Unsorted(usize),
+    TooLarge(u32),
+}
+
+fn sorted(table: &Table) -> Result<(), TableError> {
+    match table.entries.windows(2).position(|w| w[0] > w[1]) {
+        Some(i) => Err(TableError::Unsorted(i)),
+        None => Ok(()),
+    }
+}
+
+#[derive(MemConstruct, Debug)]
+#[memconstruct(validate = sorted, error = TableError)]
+struct Table {
+    #[memconstruct(validate = |len: &u32| {
+        if *len > 4096 { Err(TableError::TooLarge(*len)) } else { Ok(()) }
+    })]
+    len: u32,
+    entries: [u64; 4096],
+}
+
+#[test]
+fn validate_in_place() {
+    let table = Box::<Table>::try_heapconstruct_fallible(|c| {
+        c.set_len(4096)
+            .construct_entries(|c| c.set_all(|i| i as u64 * 3))
+            .finish()
+    })
+    .unwrap();
+    assert_eq!(table.entries[4095], 4095 * 3);
+
+    let res = Box::<Table>::try_heapconstruct_fallible(|c| {
+        c.set_len(4096)
+            .construct_entries(|c| c.set_all(|i| if i == 100 { 0 } else { i as u64 }))
+            .finish()
+    });
+    assert_eq!(res.unwrap_err().into_inner(), Some(TableError::Unsorted(99)));
+}
+
+#[test]
+fn validate_fields_first() {
+    let res = Box::<Table>::try_heapconstruct_fallible(|c| {
+        c.set_len(5000)
+            .construct_entries(|c| c.set_all(|i| 4096 - i as u64))
+            .finish()
+    });
+    // The entries are unsorted as well, but the fields are validated before the struct
+    assert_eq!(res.unwrap_err().into_inner(), Some(TableError::TooLarge(5000)));
+}
+
+#[derive(Debug, PartialEq)]
+struct OutOfRange;
+
+fn percentage(value: &u8) -> Result<(), OutOfRange> {
+    if *value <= 100 {
+        Ok(())
+    } else {
+        Err(OutOfRange)
+    }
+}
+
+#[derive(MemConstruct)]
+#[memconstruct(error = String)]
+struct Progress {
+    #[memconstruct(validate = |label: &String| {
+        if label.is_empty() { Err("empty label") } else { Ok(()) }
+    })]
+    label: String,
+    #[memconstruct(validate = percentage)]
+    #[memconstruct(default)]
+    done: u8,
+    resource: Counted,
+}
+
+impl From<OutOfRange> for String {
+    fn from(_: OutOfRange) -> Self {
+        String::from("out of range")
+    }
+}
+
+#[test]
+fn failed_validation_drops_value() {
+    static DROPS: AtomicUsize = AtomicUsize::new(0);
+    let res = Box::<Progress>::try_heapconstruct_fallible(|c| {
+        c.set_label(String::from("upload"))
+            .set_done(101)
+            .set_resource(Counted(&DROPS))
+            .finish()
+    });
+    let Err(HeapConstructError::ConstructFailed(error)) = res else {
+        panic!("the validation didn't fail")
+    };
+    assert_eq!(error, "out of range");
+    assert_eq!(DROPS.load(Ordering::SeqCst), 1);
+
+    let res = Box::<Progress>::try_heapconstruct_fallible(|c| {
+        c.set_label(String::new()).set_resource(Counted(&DROPS)).finish()
+    });
+    assert_eq!(res.err().unwrap().into_inner().unwrap(), "empty label");
+    assert_eq!(DROPS.load(Ordering::SeqCst), 2);
+
+    let progress = Box::<Progress>::try_heapconstruct_fallible(|c| {
+        c.set_label(String::from("download"))
+            .set_resource(Counted(&DROPS))
+            .finish()
+    })
+    .unwrap();
+    assert_eq!(progress.done, 0);
+    drop(progress);
+    assert_eq!(DROPS.load(Ordering::SeqCst), 3);
+}
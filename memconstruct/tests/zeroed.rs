@@ -0,0 +1,59 @@
+#![cfg(feature = "alloc")]
+
+use std::{cell::Cell, marker::PhantomData};
+
+use memconstruct::{HeapConstructExt, MemConstruct};
+
+#[derive(MemConstruct)]
+struct Pages {
+    #[memconstruct(zeroed)]
+    data: [u8; 1 << 24],
+    #[memconstruct(zeroed)]
+    used: usize,
+    name: String,
+}
+
+#[derive(MemConstruct)]
+struct Grid<T> {
+    #[memconstruct(zeroed)]
+    cells: [[T; 64]; 64],
+    marker: PhantomData<Cell<T>>,
+}
+
+#[derive(MemConstruct)]
+struct Unit;
+
+#[test]
+fn zeroed_fields_start_set() {
+    let pages = Box::<Pages>::heapconstruct_zeroed(|c| c.set_name(String::from("pages")));
+    assert!(pages.data.iter().all(|&b| b == 0));
+    assert_eq!(pages.used, 0);
+    assert_eq!(pages.name, "pages");
+}
+
+#[test]
+fn generic_zeroed_field() {
+    let grid = Box::<Grid<f64>>::heapconstruct_zeroed(|c| c.finish());
+    assert!(grid.cells.iter().flatten().all(|&cell| cell == 0.0));
+}
+
+#[test]
+fn zeroed_arrays_and_primitives() {
+    let array = Box::<[u64; 1 << 16]>::heapconstruct_zeroed(|c| c);
+    assert!(array.iter().all(|&x| x == 0));
+    let nested = Box::<[[u32; 8]; 8]>::try_heapconstruct_zeroed(|c| c).unwrap();
+    assert_eq!(*nested, [[0; 8]; 8]);
+    let prim = Box::<i32>::heapconstruct_zeroed(|c| c);
+    assert_eq!(*prim, 0);
+    let _unit = Box::<Unit>::heapconstruct_zeroed(|c| c);
+}
+
+#[test]
+fn zeroed_construction_fails() {
+    let res = Box::<Pages>::try_heapconstruct_zeroed_fallible(|c| {
+        c.try_set_name(Err::<String, _>("no name"))
+    });
+    let err = res.err().unwrap().into_inner().unwrap();
+    assert_eq!(err.field(), "name");
+    assert_eq!(*err.error(), "no name");
+}
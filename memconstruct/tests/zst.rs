@@ -0,0 +1,78 @@
+#![cfg(feature = "alloc")]
+
+use std::{
+    marker::PhantomData,
+    mem::MaybeUninit,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use memconstruct::{HeapConstructExt, MemConstruct};
+
+#[derive(MemConstruct, Debug, PartialEq)]
+struct Unit;
+
+#[derive(MemConstruct, Debug, PartialEq)]
+#[repr(align(64))]
+struct AlignedUnit {}
+
+#[derive(MemConstruct, Debug, PartialEq)]
+struct Markers<T> {
+    unit: (),
+    marker: PhantomData<T>,
+    units: [Unit; 4],
+}
+
+static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(MemConstruct)]
+struct DropUnit;
+
+impl Drop for DropUnit {
+    fn drop(&mut self) {
+        DROPS.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn construct_boxed_zst_arrays() {
+    let empty = Box::<[i32; 0]>::heapconstruct(|c| c.set_all(|_| unreachable!()));
+    assert!(empty.is_empty());
+    let units = Box::<[Unit; 8]>::heapconstruct(|c| c.memconstruct_all(|u| u));
+    assert_eq!(&*units, &[Unit, Unit, Unit, Unit, Unit, Unit, Unit, Unit]);
+    let nested = Box::<[[AlignedUnit; 3]; 2]>::heapconstruct(|c| {
+        c.memconstruct_all(|inner| inner.memconstruct_all(|u| u))
+    });
+    assert_eq!(nested.len(), 2);
+    assert_eq!(&*nested as *const _ as usize % 64, 0);
+}
+
+#[test]
+fn construct_boxed_zst_fields() {
+    let markers = Box::<Markers<String>>::heapconstruct(|c| {
+        c.construct_units(|u| u.set_all(|_| Unit)).finish()
+    });
+    assert_eq!(
+        *markers,
+        Markers {
+            unit: (),
+            marker: PhantomData,
+            units: [Unit, Unit, Unit, Unit],
+        }
+    );
+}
+
+#[test]
+fn construct_zst_in_place() {
+    let mut units = MaybeUninit::<[Unit; 3]>::uninit();
+    memconstruct::construct_maybe_uninit(&mut units, |c| c.memconstruct_all(|u| u));
+    // SAFETY: The array was constructed above
+    assert_eq!(unsafe { units.assume_init() }, [Unit, Unit, Unit]);
+}
+
+#[test]
+fn drop_boxed_zst_array() {
+    let units = Box::<[DropUnit; 5]>::heapconstruct(|c| c.memconstruct_all(|u| u));
+    assert_eq!(DROPS.load(Ordering::SeqCst), 0);
+    drop(units);
+    assert_eq!(DROPS.load(Ordering::SeqCst), 5);
+}
@@ -0,0 +1,199 @@
+//! Parsing of the `#[memconstruct(...)]` attributes.
+
+use quote::format_ident;
+use syn::{
+    meta::ParseNestedMeta, parse_quote, Attribute, Expr, Field, Ident, LitStr, Path, Token, Type,
+};
+
+/// The attributes of the struct or enum the derive is applied to.
+pub(crate) struct ItemAttrs {
+    /// Generate a constructor which checks the set fields at runtime instead of with typestate
+    pub(crate) dynamic: bool,
+    /// Checks the whole value when the construction is finished
+    pub(crate) validate: Option<Expr>,
+    /// The error returned by `finish` if a validator fails
+    pub(crate) error: Option<Type>,
+    /// Replaces the `set_` in front of the names of the setters
+    pub(crate) setter_prefix: Option<String>,
+    /// The name of the generated constructor type
+    pub(crate) constructor: Option<Ident>,
+    /// All setters take values converting into their field
+    pub(crate) into: bool,
+    /// All fallible setters take values fallibly converting into their field
+    pub(crate) try_into: bool,
+    /// The path of the memconstruct crate used by the generated code
+    pub(crate) krate: Option<Path>,
+}
+
+impl ItemAttrs {
+    pub(crate) fn from_attrs(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut item_attrs = ItemAttrs {
+            dynamic: false,
+            validate: None,
+            error: None,
+            setter_prefix: None,
+            constructor: None,
+            into: false,
+            try_into: false,
+            krate: None,
+        };
+        for attr in memconstruct_attrs(attrs) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("dynamic") {
+                    item_attrs.dynamic = true;
+                    Ok(())
+                } else if meta.path.is_ident("validate") {
+                    item_attrs.validate = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("error") {
+                    item_attrs.error = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("setter_prefix") {
+                    let prefix: LitStr = meta.value()?.parse()?;
+                    // The prefix has to form identifiers with the names of the fields
+                    if syn::parse_str::<Ident>(&format!("{}field", prefix.value())).is_err() {
+                        return Err(syn::Error::new_spanned(
+                            prefix,
+                            "the setter prefix has to start an identifier",
+                        ));
+                    }
+                    item_attrs.setter_prefix = Some(prefix.value());
+                    Ok(())
+                } else if meta.path.is_ident("constructor") {
+                    item_attrs.constructor = Some(parse_ident(&meta)?);
+                    Ok(())
+                } else if meta.path.is_ident("into") {
+                    item_attrs.into = true;
+                    Ok(())
+                } else if meta.path.is_ident("try_into") {
+                    item_attrs.try_into = true;
+                    Ok(())
+                } else if meta.path.is_ident("crate") {
+                    let path: LitStr = meta.value()?.parse()?;
+                    item_attrs.krate = Some(path.parse()?);
+                    Ok(())
+                } else {
+                    Err(meta.error("unknown memconstruct attribute"))
+                }
+            })?;
+        }
+
+        Ok(item_attrs)
+    }
+
+    /// The name of the constructor type, `<Name>MemConstructor` by default
+    pub(crate) fn constructor_name(&self, name: &Ident) -> Ident {
+        self.constructor
+            .clone()
+            .unwrap_or_else(|| format_ident!("{}MemConstructor", name))
+    }
+
+    /// The path of the memconstruct crate, `::memconstruct` by default
+    pub(crate) fn crate_path(&self) -> Path {
+        self.krate
+            .clone()
+            .unwrap_or_else(|| parse_quote! { ::memconstruct })
+    }
+
+    /// Put in front of the field names to name the setters, `set_` by default
+    pub(crate) fn setter_prefix(&self) -> &str {
+        self.setter_prefix.as_deref().unwrap_or("set_")
+    }
+}
+
+/// The attributes of a single field.
+pub(crate) struct FieldAttrs {
+    /// The value written by `finish` if the field wasn't set
+    pub(crate) default: Option<Expr>,
+    /// Checks the value of the field when the construction is finished
+    pub(crate) validate: Option<Expr>,
+    /// The name of the field in the generated functions
+    pub(crate) rename: Option<Ident>,
+    /// The setter takes any value converting into the field
+    pub(crate) into: bool,
+    /// The fallible setter takes any value fallibly converting into the field
+    pub(crate) try_into: bool,
+    /// The field is valid when zeroed and starts out set in zeroed memory
+    pub(crate) zeroed: bool,
+}
+
+impl FieldAttrs {
+    /// Parse the attributes of a field, the conversions of the item apply to all of its fields.
+    pub(crate) fn from_field(field: &Field, item_attrs: &ItemAttrs) -> syn::Result<Self> {
+        let mut attrs = FieldAttrs {
+            default: None,
+            validate: None,
+            rename: None,
+            into: item_attrs.into,
+            try_into: item_attrs.try_into,
+            zeroed: false,
+        };
+        for attr in memconstruct_attrs(&field.attrs) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("default") {
+                    attrs.default = Some(if meta.input.peek(Token![=]) {
+                        meta.value()?.parse()?
+                    } else {
+                        parse_quote! { ::core::default::Default::default() }
+                    });
+                    Ok(())
+                } else if meta.path.is_ident("validate") {
+                    attrs.validate = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("rename") {
+                    attrs.rename = Some(parse_ident(&meta)?);
+                    Ok(())
+                } else if meta.path.is_ident("into") {
+                    attrs.into = true;
+                    Ok(())
+                } else if meta.path.is_ident("try_into") {
+                    attrs.try_into = true;
+                    Ok(())
+                } else if meta.path.is_ident("zeroed") {
+                    attrs.zeroed = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("unknown memconstruct field attribute"))
+                }
+            })?;
+        }
+
+        if attrs.default.is_none() {
+            attrs.default = implicit_default(&field.ty);
+        }
+
+        Ok(attrs)
+    }
+}
+
+/// Parse an identifier given as string, like `constructor = "FooBuilder"`.
+fn parse_ident(meta: &ParseNestedMeta) -> syn::Result<Ident> {
+    let name: LitStr = meta.value()?.parse()?;
+    name.parse()
+}
+
+fn memconstruct_attrs(attrs: &[Attribute]) -> impl Iterator<Item = &Attribute> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("memconstruct"))
+}
+
+/// Fields of the unit type and `PhantomData` only have a single value and never have to be set.
+fn implicit_default(ty: &Type) -> Option<Expr> {
+    match ty {
+        Type::Tuple(tuple) if tuple.elems.is_empty() => Some(parse_quote! { () }),
+        Type::Path(path)
+            if path.qself.is_none()
+                && path
+                    .path
+                    .segments
+                    .last()
+                    .is_some_and(|segment| segment.ident == "PhantomData") =>
+        {
+            Some(parse_quote! { ::core::marker::PhantomData })
+        }
+        Type::Paren(paren) => implicit_default(&paren.elem),
+        Type::Group(group) => implicit_default(&group.elem),
+        _ => None,
+    }
+}
@@ -0,0 +1,339 @@
+//! Derive implementation for `#[memconstruct(dynamic)]`.
+//!
+//! The typestate constructor needs one generic parameter and one impl block per field, which gets
+//! slow to compile for structs with a lot of fields. The dynamic constructor instead tracks the
+//! set fields in a bitset and checks that all of them were set when `finish` is called.
+
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{Generics, Ident, LitInt};
+
+use crate::{
+    attr::ItemAttrs, brand_marker, declare_markers, generic_args, generic_params, markers_module,
+    repr::Repr, Expansion, MemConstructField, TrySetter,
+};
+
+pub(crate) fn impl_dynamic(
+    name: Ident,
+    generics: Generics,
+    fields: &[MemConstructField],
+    repr: Repr,
+    vis: syn::Visibility,
+    item_attrs: &ItemAttrs,
+) -> Expansion {
+    let constructor_name = item_attrs.constructor_name(&name);
+    let setter_prefix = item_attrs.setter_prefix();
+    let krate = item_attrs.crate_path();
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let generic_params = generic_params(&generics);
+    let generic_args = generic_args(&generics);
+    let brand_marker = brand_marker();
+    let markers = markers_module(&name);
+    let unset = quote! { #markers::Unset_fields };
+    let token_bound = quote! { #krate::__private::FieldToken };
+    let words = fields.len().div_ceil(64).max(1);
+    let write_fn = match repr.packed {
+        Some(_) => quote! { write_unaligned },
+        None => quote! { write },
+    };
+
+    let mut methods = Vec::with_capacity(fields.len());
+    let mut drops = Vec::with_capacity(fields.len());
+    let mut default_writes = Vec::new();
+    let mut missing_checks = Vec::with_capacity(fields.len());
+
+    for (i, field) in fields.iter().enumerate() {
+        let member = &field.member;
+        let field_type = &field.field_type;
+        let word = i / 64;
+        let bit = LitInt::new(&format!("{}u64", 1u64 << (i % 64)), proc_macro2::Span::call_site());
+        let is_set = quote! { (self.state.set()[#word] & #bit != 0) };
+        let field_ptr = quote! { ::core::ptr::addr_of_mut!((*self.state.ptr()).#member) };
+        let drop_field = drop_field(&field_ptr, &repr.packed);
+        let field_name = field.reported_name();
+        let setter_name = format_ident!("{}{}", setter_prefix, field_name);
+        let construct_name = format_ident!("construct_{}", field_name);
+        let init_name = format_ident!("init_{}", field_name);
+        let try_setter_name = format_ident!("try_{}{}", setter_prefix, field_name);
+        let try_construct_name = format_ident!("try_construct_{}", field_name);
+        let param_name = format_ident!("val_{}", field_name);
+        let (param_type, value) = field.setter_param(&param_name);
+        let TrySetter {
+            generic: try_generic,
+            param_type: try_param_type,
+            error: try_error,
+            result: try_result,
+            doc: try_doc,
+        } = field.try_setter(&param_name);
+        let aligned_assertion = repr.packed.as_ref().map(|packed| {
+            quote! {
+                const {
+                    ::core::assert!(
+                        ::core::mem::align_of::<#field_type>() <= #packed,
+                        "The field can't be constructed in place as it is unaligned in the \
+                        packed struct, set it instead",
+                    )
+                }
+            }
+        });
+
+        methods.push(quote! {
+            /// Set the value of the field, a value that was set before is dropped
+            pub fn #setter_name(mut self, #param_name: #param_type) -> Self {
+                let #param_name = #value;
+                // SAFETY: The bit of the field says whether it is initialized
+                unsafe {
+                    if #is_set {
+                        self.state.set_mut()[#word] &= !#bit;
+                        #drop_field
+                    }
+                    #field_ptr.#write_fn(#param_name);
+                    self.state.set_mut()[#word] |= #bit;
+                }
+                self
+            }
+
+            /// Construct the value of the field in place with its own constructor, a value that
+            /// was set before is dropped
+            ///
+            /// This is only callable if the type of the field implements `MemConstruct` and if
+            /// the field is aligned.
+            pub fn #construct_name<__F>(mut self, construct: __F) -> Self
+            where
+                // The bound is higher ranked to be checked when the function is called instead
+                // of failing for fields which don't implement `MemConstruct`
+                for<'__memconstruct> #field_type: #krate::MemConstruct,
+                __F: for<'__b> #krate::ConstructFn<'__b, #field_type>,
+            {
+                #aligned_assertion
+                // SAFETY: The bit of the field says whether it is initialized, the field is
+                // initialized by the constructor of its type
+                unsafe {
+                    if #is_set {
+                        self.state.set_mut()[#word] &= !#bit;
+                        #drop_field
+                    }
+                    #krate::construct_raw(#field_ptr, construct);
+                    self.state.set_mut()[#word] |= #bit;
+                }
+                self
+            }
+
+            #[doc = #try_doc]
+            ///
+            /// A value that was set before is dropped. An error drops the fields which were already
+            /// set and is returned together with the name of the field.
+            pub fn #try_setter_name<#try_generic>(
+                self,
+                #param_name: #try_param_type,
+            ) -> ::core::result::Result<Self, #krate::FieldError<#try_error>> {
+                match #try_result {
+                    ::core::result::Result::Ok(val) => {
+                        ::core::result::Result::Ok(self.#setter_name(val))
+                    }
+                    ::core::result::Result::Err(error) => ::core::result::Result::Err(
+                        #krate::FieldError::new(#field_name, error),
+                    ),
+                }
+            }
+
+            /// Fallibly construct the value of the field in place with its own constructor, a
+            /// value that was set before is dropped
+            ///
+            /// An error drops the fields which were already set and is returned together with the
+            /// name of the field. This is only callable if the type of the field implements
+            /// `MemConstruct` and if the field is aligned.
+            pub fn #try_construct_name<__E, __F>(
+                mut self,
+                construct: __F,
+            ) -> ::core::result::Result<Self, #krate::FieldError<__E>>
+            where
+                for<'__memconstruct> #field_type: #krate::MemConstruct,
+                __F: for<'__b> #krate::TryConstructFn<'__b, #field_type, __E>,
+            {
+                #aligned_assertion
+                // SAFETY: The bit of the field says whether it is initialized, the field is
+                // initialized by the constructor of its type if it returns `Ok`
+                unsafe {
+                    if #is_set {
+                        self.state.set_mut()[#word] &= !#bit;
+                        #drop_field
+                    }
+                    if let ::core::result::Result::Err(error) =
+                        #krate::try_construct_raw(#field_ptr, construct)
+                    {
+                        return ::core::result::Result::Err(
+                            #krate::FieldError::new(#field_name, error),
+                        );
+                    }
+                    self.state.set_mut()[#word] |= #bit;
+                }
+                ::core::result::Result::Ok(self)
+            }
+
+            /// Initialize the field through its uninitialized memory, a value that was set before
+            /// is dropped
+            ///
+            /// The passed function has to return the proof of initialization obtained from the
+            /// slot it was given. This is only callable if the field is aligned.
+            pub fn #init_name<__F>(mut self, init: __F) -> Self
+            where
+                __F: for<'__slot> FnOnce(
+                    #krate::Slot<'__slot, #field_type>,
+                ) -> #krate::Init<'__slot, #field_type>,
+            {
+                #aligned_assertion
+                // SAFETY: The bit of the field says whether it is initialized, the field is
+                // aligned and the brand of the slot is unique as the function is generic over it
+                unsafe {
+                    if #is_set {
+                        self.state.set_mut()[#word] &= !#bit;
+                        #drop_field
+                    }
+                    init(#krate::Slot::new(#field_ptr.cast()));
+                    self.state.set_mut()[#word] |= #bit;
+                }
+                self
+            }
+        });
+
+        drops.push(quote! {
+            if #is_set {
+                // SAFETY: The bit of the field says that it was initialized
+                unsafe {
+                    #drop_field
+                }
+            }
+        });
+
+        if let Some(default) = &field.attrs.default {
+            default_writes.push(quote! {
+                if !#is_set {
+                    // Evaluated outside of the unsafe block, the expression is written by the user
+                    let __value = #default;
+                    // SAFETY: The field was not set yet so it is uninitialized
+                    unsafe {
+                        #field_ptr.#write_fn(__value);
+                        self.state.set_mut()[#word] |= #bit;
+                    }
+                }
+            });
+        } else {
+            missing_checks.push(quote! {
+                if !#is_set {
+                    missing.push(#field_name);
+                }
+            });
+        }
+    }
+
+    let markers_module = declare_markers(
+        &name,
+        &vis,
+        quote! {
+            /// Marks a constructor which wasn't finished yet
+            pub struct Unset_fields;
+        },
+    );
+    let doc = format!(
+        " Constructs a `{}` in place, generated by `#[derive(MemConstruct)]`.\n\n\
+        The set fields are tracked at runtime, the token parameter is `Unset_fields` until \
+        `finish` checked that all fields are set.",
+        name,
+    );
+    let exported = quote! {
+        #[doc = #doc]
+        #[allow(clippy::all)]
+        #vis struct #constructor_name <#(#generic_params,)* __T0: #token_bound> #where_clause {
+            /// Holds one bit per field, set once the field is initialized
+            state: #krate::__private::State<
+                #name #ty_generics,
+                (#brand_marker, __T0),
+                [u64; #words],
+            >,
+        }
+
+        #markers_module
+    };
+    let impls = quote! {
+        unsafe impl #krate::__private::FieldToken for #unset {
+            const IS_SET: bool = false;
+        }
+
+        unsafe impl #impl_generics #krate::MemConstruct for #name #ty_generics
+            #where_clause
+        {
+            type Constructor<'__brand> = #constructor_name <#(#generic_args,)* #unset>;
+            type ConstructorFinishedToken<'__brand> = #constructor_name <#(#generic_args,)* ()>;
+        }
+
+        unsafe impl<#(#generic_params,)*> #krate::MemConstructConstructor
+            for #constructor_name <#(#generic_args,)* #unset> #where_clause
+        {
+            type Target = #name #ty_generics;
+
+            unsafe fn new(ptr: *mut Self::Target) -> Self {
+                Self {
+                    state: unsafe { #krate::__private::State::new(ptr, [0; #words]) },
+                }
+            }
+        }
+
+        impl<#(#generic_params,)*> #constructor_name <#(#generic_args,)* #unset>
+            #where_clause
+        {
+            #(#methods)*
+
+            /// Finish the construction, all fields which weren't set are set to their default
+            ///
+            /// Returns the fields without a default which were never set as an error.
+            pub fn finish(
+                mut self,
+            ) -> ::core::result::Result<
+                #constructor_name <#(#generic_args,)* ()>,
+                #krate::MissingFields,
+            > {
+                #(#default_writes)*
+                let mut missing = #krate::__private::Vec::new();
+                #(#missing_checks)*
+                if !missing.is_empty() {
+                    return ::core::result::Result::Err(#krate::MissingFields::new(missing));
+                }
+
+                // The finished constructor is now responsible for dropping the fields
+                let this = ::core::mem::ManuallyDrop::new(self);
+                ::core::result::Result::Ok(#constructor_name {
+                    state: unsafe {
+                        #krate::__private::State::new(this.state.ptr(), *this.state.set())
+                    },
+                })
+            }
+        }
+
+        impl<#(#generic_params,)* __T0: #token_bound> ::core::ops::Drop
+            for #constructor_name<#(#generic_args,)* __T0> #where_clause
+        {
+            fn drop(&mut self) {
+                if <__T0 as #krate::__private::FieldToken>::IS_SET {
+                    // SAFETY: The token says that the whole value was initialized
+                    unsafe {
+                        ::core::ptr::drop_in_place(self.state.ptr());
+                    }
+                } else {
+                    #(#drops)*
+                }
+            }
+        }
+    };
+
+    Expansion { exported, impls }
+}
+
+/// Drop the field behind the pointer, fields of packed structs may be unaligned so they are read
+/// out to be dropped.
+fn drop_field(field_ptr: &TokenStream2, packed: &Option<LitInt>) -> TokenStream2 {
+    match packed {
+        Some(_) => quote! { ::core::mem::drop(#field_ptr.read_unaligned()); },
+        None => quote! { ::core::ptr::drop_in_place(#field_ptr); },
+    }
+}
@@ -0,0 +1,325 @@
+//! Derive implementation for enums.
+//!
+//! Enums without fields are constructed by writing the whole variant at once. The fields of an
+//! enum can only be written in place if the layout of the enum is defined, this is the case for
+//! enums with a primitive representation (`#[repr(u8)]`, `#[repr(C)]`, `#[repr(C, u8)]`, ...).
+//! For these the layout is described by [RFC 2195], the derive recreates this layout with a tag
+//! enum and one `#[repr(C)]` struct per variant which are used to write the tag and the fields.
+//!
+//! [RFC 2195]: https://rust-lang.github.io/rfcs/2195-really-tagged-unions.html
+
+use quote::{format_ident, quote, ToTokens};
+use syn::{
+    ext::IdentExt, Attribute, DataEnum, GenericParam, Generics, Ident, Member, Visibility,
+};
+
+use crate::{
+    attr::ItemAttrs, brand_marker, declare_markers, generic_args, generic_params, markers_module,
+    repr::Repr, snake_case, Expansion, FieldsConstructor, MemConstructField,
+};
+
+pub(crate) fn memconstruct_derive_enum_impl(
+    name: Ident,
+    generics: Generics,
+    data_enum: DataEnum,
+    attrs: &[Attribute],
+    vis: Visibility,
+) -> syn::Result<Expansion> {
+    if data_enum.variants.is_empty() {
+        return Err(syn::Error::new(
+            name.span(),
+            "MemConstruct can't be derived for enums without variants",
+        ));
+    }
+
+    let repr = Repr::from_attrs(attrs)?;
+    let item_attrs = ItemAttrs::from_attrs(attrs)?;
+    if item_attrs.dynamic {
+        return Err(syn::Error::new(
+            name.span(),
+            "`#[memconstruct(dynamic)]` is only supported for structs",
+        ));
+    }
+    if item_attrs.validate.is_some() || item_attrs.error.is_some() {
+        return Err(syn::Error::new(
+            name.span(),
+            "validators are only supported for structs",
+        ));
+    }
+    let tag_repr = repr.enum_tag();
+    let has_fields = data_enum.variants.iter().any(|v| !v.fields.is_empty());
+    if has_fields && tag_repr.is_none() {
+        return Err(syn::Error::new(
+            name.span(),
+            "MemConstruct can only be derived for enums with fields if they have a primitive \
+            representation, add `#[repr(u8)]`, `#[repr(C)]` or a similar representation",
+        ));
+    }
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let generic_params = generic_params(&generics);
+    let generic_args = generic_args(&generics);
+    let brand_marker = brand_marker();
+    let constructor_name = item_attrs.constructor_name(&name);
+    let krate = item_attrs.crate_path();
+    let markers = markers_module(&name);
+    let construction_token = quote! { #markers::Unset_variant };
+    let tag_name = format_ident!("__MemConstruct{}Tag", name);
+    let repr_name = format_ident!("__MemConstruct{}Repr", name);
+    let payload_name = format_ident!("__MemConstruct{}Payload", name);
+    // Used in the layout structs to use all generic parameters of the enum
+    let marker_types = generics.params.iter().filter_map(|param| match param {
+        GenericParam::Type(ty) => {
+            let ident = &ty.ident;
+            Some(quote! { *const #ident })
+        }
+        GenericParam::Lifetime(lt) => {
+            let lifetime = &lt.lifetime;
+            Some(quote! { &#lifetime () })
+        }
+        GenericParam::Const(_) => None,
+    });
+    let marker = quote! {
+        __memconstruct_marker: ::core::marker::PhantomData<(#(#marker_types,)*)>,
+    };
+
+    let mut layout_items = Vec::new();
+    let mut payload_fields = Vec::new();
+    let mut variant_fns = Vec::new();
+    let mut variant_constructors = Vec::new();
+    let mut variant_declarations = Vec::new();
+    let mut variant_markers = Vec::new();
+
+    for variant in &data_enum.variants {
+        let variant_name = &variant.ident;
+        let variant_fn = format_ident!("variant_{}", snake_case(&variant_name.unraw().to_string()));
+
+        if variant.fields.is_empty() {
+            variant_fns.push(quote! {
+                /// Construct the variant, it doesn't have fields so it is finished directly
+                pub fn #variant_fn(self) -> #constructor_name<#(#generic_args,)* ()> {
+                    // SAFETY: The pointer is valid for writes per the contract of `new`
+                    unsafe {
+                        self.state.ptr().write(#name::#variant_name {});
+                    }
+                    #constructor_name {
+                        state: unsafe { #krate::__private::State::new(self.state.ptr(), ()) },
+                    }
+                }
+            });
+            continue;
+        }
+
+        let fields_name = format_ident!("__MemConstruct{}{}", name, variant_name);
+        // Named after the constructor of the enum so it can't collide with the constructor of a
+        // struct named `{Enum}{Variant}`
+        let variant_constructor_name = format_ident!("{}{}", constructor_name, variant_name);
+        // The fields of tuple variants are named to be placed in the layout struct
+        let fields =
+            MemConstructField::from_fields(&variant.fields, &item_attrs, |member| match member {
+                Member::Unnamed(index) => Member::Named(format_ident!("_{}", index)),
+                named => named,
+            })?;
+        if let Some(validate) = fields.iter().find_map(|field| field.attrs.validate.as_ref()) {
+            return Err(syn::Error::new_spanned(
+                validate,
+                "validators are only supported for structs",
+            ));
+        }
+        if let Some(field) = fields.iter().find(|field| field.attrs.zeroed) {
+            return Err(syn::Error::new_spanned(
+                &field.name,
+                "zeroed fields are only supported for structs",
+            ));
+        }
+        let layout_fields = fields.iter().map(|field| {
+            let member = &field.member;
+            let field_type = &field.field_type;
+            quote! { #member: #field_type, }
+        });
+        let tag_field = repr
+            .tag_in_variants()
+            .then(|| quote! { __memconstruct_tag: #tag_name, });
+        layout_items.push(quote! {
+            #[repr(C)]
+            struct #fields_name #impl_generics #where_clause {
+                #tag_field
+                #(#layout_fields)*
+                #marker
+            }
+        });
+
+        let fields_ptr = if repr.tag_in_variants() {
+            quote! { self.state.ptr().cast::<#fields_name #ty_generics>() }
+        } else {
+            payload_fields.push(quote! {
+                #variant_name: ::core::mem::ManuallyDrop<#fields_name #ty_generics>,
+            });
+            quote! {
+                ::core::ptr::addr_of_mut!(
+                    (*self.state.ptr().cast::<#repr_name #ty_generics>()).payload
+                )
+                .cast::<#fields_name #ty_generics>()
+            }
+        };
+        let constructor = FieldsConstructor {
+            token_prefix: format!("{}_", variant_name),
+            markers: &markers,
+            krate: &krate,
+            constructor_name: &variant_constructor_name,
+            vis: &vis,
+            target: quote! { #name #ty_generics },
+            fields_ptr,
+            packed: None,
+            generics: &generics,
+            fields: &fields,
+            finished_name: &constructor_name,
+            finished_type: quote! { #constructor_name<#(#generic_args,)* ()> },
+            validation: None,
+            setter_prefix: item_attrs.setter_prefix(),
+            doc: format!(
+                " Constructs the fields of the `{}::{}` variant in place, generated by \
+                `#[derive(MemConstruct)]`.",
+                name, variant_name,
+            ),
+        };
+        let construction_tokens = constructor.construction_tokens();
+        let field_names = fields.iter().map(|field| &field.name);
+
+        variant_fns.push(quote! {
+            /// Start the construction of the variant, all of its fields have to be set
+            pub fn #variant_fn(self)
+                -> #variant_constructor_name<#(#generic_args,)* #(#construction_tokens,)*>
+            {
+                // The variant is only ever written in place, this marks it as constructed for
+                // the dead code lint
+                if false {
+                    let _: #name #ty_generics = #name::#variant_name {
+                        #(#field_names: unreachable!(),)*
+                    };
+                }
+                // SAFETY: The tag is at the start of the enum for all primitive representations
+                unsafe {
+                    self.state.ptr().cast::<#tag_name>().write(#tag_name::#variant_name);
+                }
+                #variant_constructor_name {
+                    state: unsafe { #krate::__private::State::new(self.state.ptr(), ()) },
+                }
+            }
+        });
+        variant_declarations.push(constructor.declaration());
+        variant_markers.push(constructor.marker_items());
+        variant_constructors.push(constructor.into_token_stream());
+    }
+
+    if let Some(tag_repr) = &tag_repr {
+        let tags = data_enum.variants.iter().map(|variant| {
+            let variant_name = &variant.ident;
+            let discriminant = variant
+                .discriminant
+                .as_ref()
+                .map(|(eq, expr)| quote! { #eq #expr });
+            quote! { #variant_name #discriminant, }
+        });
+        layout_items.push(quote! {
+            #[repr(#tag_repr)]
+            enum #tag_name {
+                #(#tags)*
+            }
+        });
+    }
+
+    if !payload_fields.is_empty() {
+        layout_items.push(quote! {
+            #[repr(C)]
+            struct #repr_name #impl_generics #where_clause {
+                tag: #tag_name,
+                payload: #payload_name #ty_generics,
+            }
+
+            #[repr(C)]
+            union #payload_name #impl_generics #where_clause {
+                #(#payload_fields)*
+            }
+        });
+    }
+
+    let markers_module = declare_markers(
+        &name,
+        &vis,
+        quote! {
+            /// Marks a constructor whose variant wasn't chosen yet
+            pub struct Unset_variant;
+
+            #(#variant_markers)*
+        },
+    );
+    let doc = format!(
+        " Constructs a `{}` in place, generated by `#[derive(MemConstruct)]`.\n\n\
+        The token parameter is `Unset_variant` until a variant is constructed and \
+        `memconstruct::Set` afterwards.",
+        name,
+    );
+    let exported = quote! {
+        #[doc = #doc]
+        #[allow(clippy::all)]
+        #vis struct #constructor_name
+            <#(#generic_params,)* __T0: #krate::__private::FieldToken> #where_clause
+        {
+            state: #krate::__private::State<#name #ty_generics, (#brand_marker, __T0)>,
+        }
+
+        #(#variant_declarations)*
+
+        #markers_module
+    };
+    let impls = quote! {
+        #(#layout_items)*
+
+        unsafe impl #krate::__private::FieldToken for #construction_token {
+            const IS_SET: bool = false;
+        }
+
+        impl<#(#generic_params,)* __T0: #krate::__private::FieldToken> ::core::ops::Drop
+            for #constructor_name<#(#generic_args,)* __T0> #where_clause
+        {
+            fn drop(&mut self) {
+                if <__T0 as #krate::__private::FieldToken>::IS_SET {
+                    // SAFETY: The token says that the whole enum was initialized
+                    unsafe {
+                        ::core::ptr::drop_in_place(self.state.ptr());
+                    }
+                }
+            }
+        }
+
+        unsafe impl #impl_generics #krate::MemConstruct for #name #ty_generics
+            #where_clause
+        {
+            type Constructor<'__brand> = #constructor_name <#(#generic_args,)* #construction_token>;
+            type ConstructorFinishedToken<'__brand> = #constructor_name <#(#generic_args,)* ()>;
+        }
+
+        unsafe impl<#(#generic_params,)*> #krate::MemConstructConstructor
+            for #constructor_name <#(#generic_args,)* #construction_token> #where_clause
+        {
+            type Target = #name #ty_generics;
+
+            unsafe fn new(ptr: *mut Self::Target) -> Self {
+                Self {
+                    state: unsafe { #krate::__private::State::new(ptr, ()) },
+                }
+            }
+        }
+
+        impl<#(#generic_params,)*> #constructor_name <#(#generic_args,)* #construction_token>
+            #where_clause
+        {
+            #(#variant_fns)*
+        }
+
+        #(#variant_constructors)*
+    };
+
+    Ok(Expansion { exported, impls })
+}
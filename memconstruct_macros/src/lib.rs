@@ -1,35 +1,58 @@
 use proc_macro2::TokenStream as TokenStream2;
 use quote::{quote, ToTokens};
-use syn::{Data, DataStruct, DeriveInput, Fields, Generics, Ident, Type, Visibility, Member, Index};
+use syn::{
+    ext::IdentExt, spanned::Spanned, Attribute, Data, DataStruct, DeriveInput, Expr, Fields,
+    GenericParam, Generics, Ident, Index, LitInt, Member, Path, Type, Visibility,
+};
 
-#[proc_macro_derive(MemConstruct)]
+mod attr;
+mod dynamic;
+mod enums;
+mod repr;
+
+use attr::{FieldAttrs, ItemAttrs};
+use repr::Repr;
+
+#[proc_macro_derive(MemConstruct, attributes(memconstruct))]
 pub fn memconstruct_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let input = syn::parse_macro_input!(input as DeriveInput);
     let item_name = input.ident;
     let data = input.data;
 
-    let module_name = Ident::new(
-        &format!("__memconstruct__impl__{}", item_name),
-        item_name.span(),
-    );
     let impl_tokens = match data {
         Data::Struct(data_struct) => memconstruct_derive_struct_impl(
             item_name.clone(),
             input.generics,
             data_struct,
+            &input.attrs,
+            input.vis,
+        ),
+        Data::Enum(data_enum) => enums::memconstruct_derive_enum_impl(
+            item_name.clone(),
+            input.generics,
+            data_enum,
+            &input.attrs,
             input.vis,
         ),
-        data => todo!("Currently not supported: {:?}", data),
+        Data::Union(data_union) => Err(syn::Error::new(
+            data_union.union_token.span,
+            "MemConstruct can't be derived for unions",
+        )),
+    };
+    let Expansion { exported, impls } = match impl_tokens {
+        Ok(expansion) => expansion,
+        Err(e) => return e.into_compile_error().into(),
     };
 
+    // The anonymous constant keeps the implementation details out of the scope of the item while
+    // they still resolve every path like the item itself, also inside of function bodies
     let expanded = quote! {
-        #[doc(hidden)]
-        #[allow(non_snake_case)]
-        mod #module_name {
-            #![allow(clippy::all, warnings, unused, non_snake_case, non_camel_case_types)]
-            use super:: #item_name ;
-            #impl_tokens
-        }
+        #exported
+
+        #[allow(clippy::all, warnings, unused, non_snake_case, non_camel_case_types)]
+        const _: () = {
+            #impls
+        };
     };
 
     // panic!("{}", expanded);
@@ -37,212 +60,1031 @@ pub fn memconstruct_derive(input: proc_macro::TokenStream) -> proc_macro::TokenS
     expanded.into()
 }
 
+/// The code generated for an item.
+struct Expansion {
+    /// Declared next to the item so that other code can name them, the constructor types and the
+    /// module with their typestate markers
+    exported: TokenStream2,
+    /// The implementations, placed in an anonymous constant
+    impls: TokenStream2,
+}
+
 fn memconstruct_derive_struct_impl(
     name: Ident,
     generics: Generics,
     data_struct: DataStruct,
+    attrs: &[Attribute],
     vis: Visibility,
-) -> impl ToTokens {
-    let constructor_name = Ident::new(&format!("{}MemConstructor", name), name.span());
+) -> syn::Result<Expansion> {
+    let repr = Repr::from_attrs(attrs)?;
+    let mut item_attrs = ItemAttrs::from_attrs(attrs)?;
+    let constructor_name = item_attrs.constructor_name(&name);
 
     let fields = match &data_struct.fields {
-        // A struct where all fields are named (filter_map won't skip anythin)
-        Fields::Named(fields) => fields
-            .named
-            .iter()
-            .filter_map(|field| {
-                Some(MemConstructField {
-                    name: Member::Named(field.ident.clone()?),
-                    field_type: field.ty.clone(),
-                })
-            })
-            .collect::<Vec<_>>(),
-        // A struct where all fields are unnamed
-        Fields::Unnamed(fields) => fields
-            .unnamed
-            .iter()
-            .enumerate()
-            .map(|(i, field)| MemConstructField {
-                name: Member::Unnamed(Index::from(i)),
-                field_type: field.ty.clone(),
-            })
-            .collect::<Vec<_>>(),
-        Fields::Unit => return impl_zst(name, constructor_name, generics, quote! { Self }),
+        Fields::Unit => Vec::new(),
+        fields => MemConstructField::from_fields(fields, &item_attrs, |member| member)?,
     };
+    let validation = Validation::from_attrs(
+        &name,
+        item_attrs.validate.take(),
+        item_attrs.error.take(),
+        &fields,
+    )?;
+    if validation.is_some() && (fields.is_empty() || item_attrs.dynamic) {
+        return Err(syn::Error::new(
+            name.span(),
+            "validators are only supported for structs with fields and typestate constructors",
+        ));
+    }
+
+    if let Some(field) = fields
+        .iter()
+        .find(|field| field.attrs.zeroed && item_attrs.dynamic)
+    {
+        return Err(syn::Error::new_spanned(
+            &field.name,
+            "zeroed fields are only supported for typestate constructors",
+        ));
+    }
+
+    if matches!(data_struct.fields, Fields::Unit) {
+        return Ok(impl_zst(
+            name,
+            constructor_name,
+            generics,
+            vis,
+            &item_attrs.crate_path(),
+        ));
+    }
 
-    impl_struct(name, constructor_name, generics, &fields, vis)
+    if item_attrs.dynamic && !fields.is_empty() {
+        return Ok(dynamic::impl_dynamic(
+            name,
+            generics,
+            &fields,
+            repr,
+            vis,
+            &item_attrs,
+        ));
+    }
+
+    Ok(impl_struct(
+        name,
+        generics,
+        &fields,
+        repr,
+        vis,
+        &item_attrs,
+        validation,
+    ))
+}
+
+/// The validators run by `finish` and the error it returns if one of them fails.
+struct Validation {
+    /// Validator of the whole value, run after the validators of the fields
+    validate: Option<Expr>,
+    error: Type,
+}
+
+impl Validation {
+    /// Collect the validators of an item, they require an error type if there are any.
+    fn from_attrs(
+        name: &Ident,
+        validate: Option<Expr>,
+        error: Option<Type>,
+        fields: &[MemConstructField],
+    ) -> syn::Result<Option<Self>> {
+        let has_validators =
+            validate.is_some() || fields.iter().any(|field| field.attrs.validate.is_some());
+        match error {
+            Some(error) if has_validators => Ok(Some(Validation { validate, error })),
+            Some(error) => Err(syn::Error::new_spanned(
+                error,
+                "the error type is only used by validators",
+            )),
+            None if has_validators => Err(syn::Error::new(
+                name.span(),
+                "validators require the error type returned by `finish`, add \
+                `#[memconstruct(error = Type)]`",
+            )),
+            None => Ok(None),
+        }
+    }
 }
 
 struct MemConstructField {
+    /// The name of the field used for the generated functions
     name: Member,
+    /// The field inside the memory the constructor writes to
+    member: Member,
     field_type: Type,
+    attrs: FieldAttrs,
+}
+
+impl MemConstructField {
+    /// Collect the fields of a struct or enum variant, `member` maps the name of each field to the
+    /// place it is written to.
+    fn from_fields(
+        fields: &Fields,
+        item_attrs: &ItemAttrs,
+        member: impl Fn(Member) -> Member,
+    ) -> syn::Result<Vec<Self>> {
+        fields
+            .iter()
+            .enumerate()
+            .map(|(i, field)| {
+                let name = match &field.ident {
+                    Some(ident) => Member::Named(ident.clone()),
+                    None => Member::Unnamed(Index::from(i)),
+                };
+                Ok(MemConstructField {
+                    member: member(name.clone()),
+                    name,
+                    field_type: field.ty.clone(),
+                    attrs: FieldAttrs::from_field(field, item_attrs)?,
+                })
+            })
+            .collect()
+    }
+
+    /// The name of the field in the generated functions and in errors, tuple fields are named by
+    /// index unless they are renamed.
+    fn reported_name(&self) -> String {
+        match (&self.attrs.rename, &self.name) {
+            (Some(rename), _) => rename.unraw().to_string(),
+            (None, Member::Named(ident)) => ident.unraw().to_string(),
+            (None, Member::Unnamed(index)) => index.index.to_string(),
+        }
+    }
+
+    /// The type of the setter parameter and the expression turning it into the field value,
+    /// `#[memconstruct(into)]` setters take anything that converts into the field.
+    fn setter_param(&self, param: &Ident) -> (TokenStream2, TokenStream2) {
+        let field_type = &self.field_type;
+        if self.attrs.into {
+            (
+                quote! { impl ::core::convert::Into<#field_type> },
+                quote! { ::core::convert::Into::into(#param) },
+            )
+        } else {
+            (quote! { #field_type }, quote! { #param })
+        }
+    }
+
+    /// The signature of the fallible setter, `#[memconstruct(try_into)]` setters take anything
+    /// that fallibly converts into the field instead of a `Result`.
+    fn try_setter(&self, param: &Ident) -> TrySetter {
+        let field_type = &self.field_type;
+        if self.attrs.try_into {
+            TrySetter {
+                generic: quote! { __V: ::core::convert::TryInto<#field_type> },
+                param_type: quote! { __V },
+                error: quote! { <__V as ::core::convert::TryInto<#field_type>>::Error },
+                result: quote! { ::core::convert::TryInto::try_into(#param) },
+                doc: " Set the field to the value if it converts into the type of the field",
+            }
+        } else {
+            TrySetter {
+                generic: quote! { __E },
+                param_type: quote! { ::core::result::Result<#field_type, __E> },
+                error: quote! { __E },
+                result: quote! { #param },
+                doc: " Set the value of the field if it is `Ok`",
+            }
+        }
+    }
+}
+
+/// The signature of a generated `try_set_<field>` function.
+struct TrySetter {
+    generic: TokenStream2,
+    param_type: TokenStream2,
+    /// The error in the returned `FieldError`
+    error: TokenStream2,
+    /// The parameter as `Result` of the field
+    result: TokenStream2,
+    doc: &'static str,
 }
 
 fn impl_struct(
     name: Ident,
-    constructor_name: Ident,
     generics: Generics,
     fields: &[MemConstructField],
+    repr: Repr,
     vis: Visibility,
-) -> TokenStream2 {
+    item_attrs: &ItemAttrs,
+    validation: Option<Validation>,
+) -> Expansion {
+    let constructor_name = item_attrs.constructor_name(&name);
+    let krate = item_attrs.crate_path();
     if fields.is_empty() {
-        return impl_zst(name, constructor_name, generics, quote! { Self {} });
+        return impl_zst(name, constructor_name, generics, vis, &krate);
     }
 
     let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
-    let construction_tokens = fields
+    let generic_params = generic_params(&generics);
+    let generic_args = generic_args(&generics);
+    let markers = markers_module(&name);
+    // The constructor of validated structs is only finished once `finish` ran the validators,
+    // setting all fields isn't enough
+    let (finished_token, validated_token, validated_impl) = match validation {
+        Some(_) => (
+            quote! { #markers::Validated },
+            Some(quote! {
+                /// Marks a constructor whose validators passed
+                pub struct Validated;
+            }),
+            Some(quote! {
+                unsafe impl #krate::__private::FieldToken for #markers::Validated {
+                    const IS_SET: bool = true;
+                }
+            }),
+        ),
+        None => (quote! { () }, None, None),
+    };
+    let finished_tokens = fields.iter().map(|_| &finished_token).collect::<Vec<_>>();
+    let constructor = FieldsConstructor {
+        token_prefix: String::new(),
+        markers: &markers,
+        krate: &krate,
+        constructor_name: &constructor_name,
+        vis: &vis,
+        target: quote! { #name #ty_generics },
+        fields_ptr: quote! { self.state.ptr() },
+        packed: repr.packed,
+        generics: &generics,
+        fields,
+        finished_name: &constructor_name,
+        finished_type: quote! { #constructor_name <#(#generic_args,)* #(#finished_tokens,)*> },
+        validation: validation.as_ref(),
+        setter_prefix: item_attrs.setter_prefix(),
+        doc: format!(" Constructs a `{}` in place, generated by `#[derive(MemConstruct)]`.", name),
+    };
+    let construction_tokens = constructor.construction_tokens();
+    // Zeroed fields start out set in zeroed memory, their types have to be valid when zeroed
+    let zeroed_tokens = fields
         .iter()
-        .map(|field| memconstruct_token(&name, &field.name))
-        .collect::<Vec<_>>();
-    let finished_tokens = fields.iter().map(|_| quote! { (), });
-    // The tokens T0 til TN used to mark DC vals on the impls
-    let impl_token_generics = (0..fields.len())
-        .map(|i| {
-            let ident = quote::format_ident!("T{}", i);
-            quote! { #ident }
+        .zip(&construction_tokens)
+        .map(|(field, token)| match field.attrs.zeroed {
+            true => quote! { () },
+            false => quote! { #token },
         })
         .collect::<Vec<_>>();
-    let mut impls = Vec::with_capacity(fields.len());
-
-    // Create all impl blocks
-    for (i, field) in fields.iter().enumerate() {
-        let impl_token_generics = impl_token_generics
-            .get(0..impl_token_generics.len().saturating_sub(1))
-            .unwrap_or_else(|| {
-                unreachable!(
-                    "This cuts of the last Token from the Vector, this code won't be \
-                    reached for empty Vectors"
-                )
-            });
-        // let after = fields
-        //     .len()
-        //     .checked_sub(i)
-        //     .unwrap_or_else(|| unreachable!("The enumeration will never reach len"));
-
-        // TODO make heapconstruction composable
-        let field_name = &field.name;
-        let param_name = quote::format_ident!("val_{}", field_name);
+    let mut zeroed_generics = generics.clone();
+    let zeroed_where_clause = zeroed_generics.make_where_clause();
+    for field in fields.iter().filter(|field| field.attrs.zeroed) {
         let field_type = &field.field_type;
-        let before_tokens = impl_token_generics
+        // The bound is reported at its path, which points to the field this way
+        let krate = krate
+            .to_token_stream()
+            .into_iter()
+            .map(|mut token| {
+                token.set_span(field_type.span());
+                token
+            })
+            .collect::<TokenStream2>();
+        zeroed_where_clause.predicates.push(syn::parse_quote_spanned! { field_type.span()=>
+            #field_type: #krate::zeroed::Zeroable
+        });
+    }
+
+    let marker_items = constructor.marker_items();
+    let markers_module = declare_markers(&name, &vis, quote! { #validated_token #marker_items });
+    let declaration = constructor.declaration();
+
+    let exported = quote! {
+        #declaration
+
+        #markers_module
+    };
+    let impls = quote! {
+        #validated_impl
+
+        #constructor
+
+        unsafe impl #impl_generics #krate::MemConstruct for #name #ty_generics
+            #where_clause
+        {
+            type Constructor<'__brand> =
+                #constructor_name <#(#generic_args,)* #(#construction_tokens,)*> ;
+            type ConstructorFinishedToken<'__brand> =
+                #constructor_name <#(#generic_args,)* #(#finished_tokens,)*> ;
+        }
+
+        unsafe impl<#(#generic_params,)*> #krate::MemConstructConstructor
+            for #constructor_name <#(#generic_args,)* #(#construction_tokens,)*> #where_clause
+        {
+            type Target = #name #ty_generics;
+
+            unsafe fn new(ptr: *mut Self::Target) -> Self {
+                Self {
+                    state: unsafe { #krate::__private::State::new(ptr, ()) },
+                }
+            }
+        }
+
+        unsafe impl #impl_generics #krate::zeroed::ZeroedConstruct for #name #ty_generics
+            #zeroed_where_clause
+        {
+            type ZeroedConstructor<'__brand> =
+                #constructor_name <#(#generic_args,)* #(#zeroed_tokens,)*> ;
+
+            unsafe fn zeroed_constructor<'__brand>(
+                ptr: *mut Self,
+            ) -> Self::ZeroedConstructor<'__brand> {
+                #constructor_name {
+                    state: unsafe { #krate::__private::State::new(ptr, ()) },
+                }
+            }
+        }
+    };
+
+    Expansion { exported, impls }
+}
+
+/// A typestate constructor which has to set each of its fields exactly once.
+///
+/// This is the constructor of structs and of the variants of enums.
+struct FieldsConstructor<'a> {
+    /// Prepended to the field names to build the construction tokens, the tokens are named
+    /// `Unset_<prefix><field>` to show up readably in type errors
+    token_prefix: String,
+    /// The module next to the item which holds the construction tokens
+    markers: &'a Ident,
+    /// The path of the memconstruct crate
+    krate: &'a Path,
+    constructor_name: &'a Ident,
+    vis: &'a Visibility,
+    /// The type the pointer of the constructor points to
+    target: TokenStream2,
+    /// An expression turning `self.state.ptr()` into a pointer to the memory that holds the fields
+    fields_ptr: TokenStream2,
+    /// The alignment the fields are packed to, fields in packed memory may be unaligned
+    packed: Option<LitInt>,
+    generics: &'a Generics,
+    fields: &'a [MemConstructField],
+    /// The constructor returned by `finish` once all fields without a default are set
+    finished_name: &'a Ident,
+    finished_type: TokenStream2,
+    /// The validators `finish` runs on the finished value, only supported for structs
+    validation: Option<&'a Validation>,
+    /// Put in front of the field names to name the setters, `set_` by default
+    setter_prefix: &'a str,
+    /// The documentation of the constructor type
+    doc: String,
+}
+
+impl FieldsConstructor<'_> {
+    /// The tokens marking each field as not yet set
+    fn construction_tokens(&self) -> Vec<TokenStream2> {
+        let markers = self.markers;
+        self.fields
             .iter()
-            .clone()
-            .take(i)
+            .map(|field| {
+                let token = memconstruct_token(&self.token_prefix, &field.reported_name());
+                quote! { #markers::#token }
+            })
+            .collect()
+    }
+
+    /// The declarations of the construction tokens, placed in the markers module
+    ///
+    /// Their `FieldToken` implementations are part of the other implementations, the crate path may
+    /// be relative to the item and doesn't resolve inside of the markers module.
+    fn marker_items(&self) -> TokenStream2 {
+        let tokens = self.fields.iter().map(|field| {
+            let field_name = field.reported_name();
+            let token = memconstruct_token(&self.token_prefix, &field_name);
+            let doc = format!(" Marks the field `{}` as not set", field_name);
+            quote! {
+                #[doc = #doc]
+                pub struct #token;
+            }
+        });
+        quote! { #(#tokens)* }
+    }
+
+    /// The declaration of the constructor type, placed next to the item
+    fn declaration(&self) -> TokenStream2 {
+        let krate = self.krate;
+        let FieldsConstructor {
+            constructor_name,
+            vis,
+            target,
+            generics,
+            fields,
+            ..
+        } = self;
+        let where_clause = &generics.where_clause;
+        let generic_params = generic_params(generics);
+        let token_generics = (0..fields.len())
+            .map(|i| quote::format_ident!("__T{}", i))
             .collect::<Vec<_>>();
-        let after_tokens = impl_token_generics.iter().skip(i).collect::<Vec<_>>();
-        let impl_token_generics = impl_token_generics;
-        let construction_token = construction_tokens
-            .get(i)
-            .unwrap_or_else(|| unreachable!("There should be a construction token for each field"));
-        let setter_name = quote::format_ident!("set_{}", field.name);
-        let with_pointer_fn_name = quote::format_ident!("set_{}_with_pointer", field_name);
-        let impl_quote = quote! {
-            impl < #(#impl_token_generics,)* > #constructor_name
-                < #(#before_tokens,)* #construction_token,  #(#after_tokens,)* >
+        let brand_marker = brand_marker();
+        let doc = format!(
+            "{}\n\nEach field has a token parameter, which is either its `Unset_` marker or \
+            `memconstruct::Set`.",
+            self.doc,
+        );
+
+        quote! {
+            #[doc = #doc]
+            #[allow(non_camel_case_types)]
+            #[allow(clippy::all)]
+            #vis struct #constructor_name
+                <#(#generic_params,)* #(#token_generics: #krate::__private::FieldToken,)*>
+                #where_clause
             {
-                /// Set the value of the field
-                pub fn #setter_name(self, #param_name: #field_type)
-                 -> #constructor_name<#(#before_tokens,)* (), #(#after_tokens,)*>
+                state: #krate::__private::State<#target, (#brand_marker, #(#token_generics,)*)>,
+            }
+        }
+    }
+
+    /// The `finish` function which writes the defaults of all unset fields
+    fn finish_impl(&self) -> TokenStream2 {
+        let krate = self.krate;
+        let FieldsConstructor {
+            constructor_name,
+            fields_ptr,
+            generics,
+            fields,
+            finished_name,
+            finished_type,
+            validation,
+            ..
+        } = self;
+        let where_clause = &generics.where_clause;
+        let generic_params = generic_params(generics);
+        let generic_args = generic_args(generics);
+        let write_fn = self.write_fn();
+        let aligned_assertion = |field_type: &Type| {
+            self.packed.as_ref().map(|packed| {
+                quote! {
+                    const {
+                        ::core::assert!(
+                            ::core::mem::align_of::<#field_type>() <= #packed,
+                            "The field can't be validated as it is unaligned in the packed \
+                            struct",
+                        )
+                    }
+                }
+            })
+        };
+        let tokens = (0..fields.len())
+            .map(|i| quote::format_ident!("__T{}", i))
+            .collect::<Vec<_>>();
+        let mut required_tokens = Vec::new();
+        let mut default_writes = Vec::new();
+
+        for (field, token) in fields.iter().zip(&tokens) {
+            let Some(default) = &field.attrs.default else {
+                required_tokens.push(token);
+                continue;
+            };
+            let member = &field.member;
+            default_writes.push(quote! {
+                if !<#token as #krate::__private::FieldToken>::IS_SET {
+                    // Evaluated outside of the unsafe block, the expression is written by the user
+                    let __value = #default;
+                    // SAFETY: The field was not set yet so it is uninitialized
+                    unsafe {
+                        ::core::ptr::addr_of_mut!((*#fields_ptr).#member).#write_fn(__value);
+                    }
+                }
+            });
+        }
+
+        let finished = quote! {
+            // The finished constructor is now responsible for dropping the fields
+            let this = ::core::mem::ManuallyDrop::new(self);
+            let finished = #finished_name {
+                state: unsafe { #krate::__private::State::new(this.state.ptr(), ()) },
+            };
+        };
+        let Some(Validation { validate, error }) = validation else {
+            return quote! {
+                impl<#(#generic_params,)* #(#tokens: #krate::__private::FieldToken,)*>
+                    #constructor_name<#(#generic_args,)* #(#tokens,)*>
+                    #where_clause
                 {
-                 // SAFETY: we write to the field via addr_of_mut TODO packed types need unaligned
-                 unsafe {
-                     ::core::ptr::addr_of_mut!((*self.ptr).#field_name).write(#param_name);
-                 }
-                 #constructor_name::<#(#before_tokens,)* (), #(#after_tokens,)*> {
-                     ptr: self.ptr,
-                     boo_scary: ::core::marker::PhantomData::default(),
-                  }
+                    /// Finish the construction, all fields which weren't set are set to their
+                    /// default
+                    ///
+                    /// Every field without a default has to be set before.
+                    pub fn finish(self) -> #finished_type
+                    where
+                        // Reports every field which is still unset
+                        #(#required_tokens: #krate::__private::IsSet,)*
+                    {
+                        #(#default_writes)*
+                        #finished
+                        finished
+                    }
                 }
+            };
+        };
 
-                /// Set the value of the field through the pointer
-                ///
-                /// # SAFETY
-                ///
-                /// This is marked unsafe as we have to rely on the pointer being actually written
-                pub unsafe fn #with_pointer_fn_name(self, init: impl FnOnce(*mut #field_type)) {
-                    init(::core::ptr::addr_of_mut!((*self.ptr).#field_name))
+        let field_validations = fields.iter().filter_map(|field| {
+            let validate = field.attrs.validate.as_ref()?;
+            let member = &field.member;
+            let aligned_assertion = aligned_assertion(&field.field_type);
+            Some(quote! {
+                #aligned_assertion
+                // SAFETY: The field is initialized and aligned
+                let field = unsafe { &*::core::ptr::addr_of!((*finished.state.ptr()).#member) };
+                if let ::core::result::Result::Err(error) = (#validate)(field) {
+                    return ::core::result::Result::Err(::core::convert::From::from(error));
+                }
+            })
+        });
+        let validation = validate.as_ref().map(|validate| {
+            quote! {
+                // SAFETY: The whole value is initialized
+                let value = unsafe { &*finished.state.ptr() };
+                if let ::core::result::Result::Err(error) = (#validate)(value) {
+                    return ::core::result::Result::Err(::core::convert::From::from(error));
                 }
             }
-        };
+        });
 
-        impls.push(impl_quote);
+        quote! {
+            impl<#(#generic_params,)* #(#tokens: #krate::__private::FieldToken,)*>
+                #constructor_name<#(#generic_args,)* #(#tokens,)*>
+                #where_clause
+            {
+                /// Finish the construction and validate the value, all fields which weren't set
+                /// are set to their default
+                ///
+                /// Every field without a default has to be set before. If a validator fails the
+                /// value is dropped and the error is returned.
+                pub fn finish(self) -> ::core::result::Result<#finished_type, #error>
+                where
+                    // Reports every field which is still unset
+                    #(#required_tokens: #krate::__private::IsSet,)*
+                {
+                    #(#default_writes)*
+                    #finished
+                    #(#field_validations)*
+                    #validation
+                    ::core::result::Result::Ok(finished)
+                }
+            }
+        }
     }
 
-    let constructor_visibility = match vis {
-        Visibility::Inherited => quote! { pub(super) },
-        vis => quote! { #vis },
-    };
+    /// The `Drop` implementation which drops all fields that were set
+    fn drop_impl(&self) -> TokenStream2 {
+        let krate = self.krate;
+        let FieldsConstructor {
+            constructor_name,
+            fields_ptr,
+            packed,
+            generics,
+            fields,
+            ..
+        } = self;
+        let where_clause = &generics.where_clause;
+        let generic_params = generic_params(generics);
+        let generic_args = generic_args(generics);
+        let tokens = (0..fields.len())
+            .map(|i| quote::format_ident!("__T{}", i))
+            .collect::<Vec<_>>();
+        let drops = fields.iter().zip(&tokens).map(|(field, token)| {
+            let member = &field.member;
+            let drop_field = match packed {
+                // Fields of packed structs may be unaligned so they are read out to be dropped
+                Some(_) => quote! {
+                    ::core::mem::drop(
+                        ::core::ptr::addr_of_mut!((*#fields_ptr).#member).read_unaligned(),
+                    );
+                },
+                None => quote! {
+                    ::core::ptr::drop_in_place(::core::ptr::addr_of_mut!((*#fields_ptr).#member));
+                },
+            };
+            quote! {
+                if <#token as #krate::__private::FieldToken>::IS_SET {
+                    // SAFETY: The token of the field says that it was initialized
+                    unsafe {
+                        #drop_field
+                    }
+                }
+            }
+        });
 
-    quote! {
-        #(
-            #[allow(non_camel_case_types)]
-            #[allow(clippy::all)]
-            pub struct #construction_tokens ;
-        )*
+        quote! {
+            impl<#(#generic_params,)* #(#tokens: #krate::__private::FieldToken,)*>
+                ::core::ops::Drop for #constructor_name<#(#generic_args,)* #(#tokens,)*>
+                #where_clause
+            {
+                fn drop(&mut self) {
+                    #(#drops)*
+                }
+            }
+        }
+    }
 
-        #[allow(non_camel_case_types)]
-        #[allow(clippy::all)]
-        #constructor_visibility struct #constructor_name <#(#impl_token_generics,)*> {
-            ptr: *mut #name,
-            boo_scary: ::core::marker::PhantomData::<(#(#impl_token_generics,)*)>,
+    /// The function used to write to fields
+    fn write_fn(&self) -> TokenStream2 {
+        match self.packed {
+            Some(_) => quote! { write_unaligned },
+            None => quote! { write },
         }
+    }
+}
 
-        unsafe impl #impl_generics ::memconstruct::MemConstruct for #name #ty_generics
-            #where_clause
-        {
-            type Constructor = #constructor_name <#(#construction_tokens,)*> ;
-            type ConstructorFinishedToken = #constructor_name <#(#finished_tokens)*> ;
+impl ToTokens for FieldsConstructor<'_> {
+    fn to_tokens(&self, tokens: &mut TokenStream2) {
+        let krate = self.krate;
+        let FieldsConstructor {
+            constructor_name,
+            fields_ptr,
+            packed,
+            generics,
+            fields,
+            setter_prefix,
+            ..
+        } = self;
+        let where_clause = &generics.where_clause;
+        let generic_params = generic_params(generics);
+        let generic_args = generic_args(generics);
+        let construction_tokens = self.construction_tokens();
+        // The tokens __T0 til __TN used to mark DC vals on the impls, prefixed to not collide with
+        // the generic parameters of the struct itself
+        let impl_token_generics = (0..fields.len())
+            .map(|i| {
+                let ident = quote::format_ident!("__T{}", i);
+                quote! { #ident }
+            })
+            .collect::<Vec<_>>();
+        let token_bound = quote! { #krate::__private::FieldToken };
+        let mut impls = Vec::with_capacity(fields.len());
+
+        // Create all impl blocks
+        for (i, field) in fields.iter().enumerate() {
+            let impl_token_generics = impl_token_generics
+                .get(0..impl_token_generics.len().saturating_sub(1))
+                .unwrap_or_else(|| {
+                    unreachable!(
+                        "This cuts of the last Token from the Vector, this code won't be \
+                        reached for empty Vectors"
+                    )
+                });
+
+            let field_name = field.reported_name();
+            let member = &field.member;
+            let param_name = quote::format_ident!("val_{}", field_name);
+            let (param_type, value) = field.setter_param(&param_name);
+            let TrySetter {
+                generic: try_generic,
+                param_type: try_param_type,
+                error: try_error,
+                result: try_result,
+                doc: try_doc,
+            } = field.try_setter(&param_name);
+            let field_type = &field.field_type;
+            let before_tokens = impl_token_generics
+                .iter()
+                .clone()
+                .take(i)
+                .collect::<Vec<_>>();
+            let after_tokens = impl_token_generics.iter().skip(i).collect::<Vec<_>>();
+            let construction_token = construction_tokens.get(i).unwrap_or_else(|| {
+                unreachable!("There should be a construction token for each field")
+            });
+            let setter_name = quote::format_ident!("{}{}", setter_prefix, field_name);
+            let with_pointer_fn_name =
+                quote::format_ident!("{}{}_with_pointer", setter_prefix, field_name);
+            let construct_name = quote::format_ident!("construct_{}", field_name);
+            let init_name = quote::format_ident!("init_{}", field_name);
+            let try_setter_name = quote::format_ident!("try_{}{}", setter_prefix, field_name);
+            let try_construct_name = quote::format_ident!("try_construct_{}", field_name);
+            let write_fn = self.write_fn();
+            // Nested constructors require an aligned pointer, which fields of packed structs only
+            // have if their alignment isn't bigger than the packing
+            let aligned_assertion = packed.as_ref().map(|packed| {
+                quote! {
+                    const {
+                        ::core::assert!(
+                            ::core::mem::align_of::<#field_type>() <= #packed,
+                            "The field can't be constructed in place as it is unaligned in the \
+                            packed struct, set it instead",
+                        )
+                    }
+                }
+            });
+            let unaligned_doc = packed.as_ref().map(|_| {
+                quote! {
+                    ///
+                    /// The pointer may be unaligned as the fields of this type are packed.
+                }
+            });
+            let impl_quote = quote! {
+                impl < #(#generic_params,)* #(#impl_token_generics: #token_bound,)* > #constructor_name
+                    < #(#generic_args,)* #(#before_tokens,)* #construction_token,  #(#after_tokens,)* >
+                    #where_clause
+                {
+                    /// Set the value of the field
+                    pub fn #setter_name(self, #param_name: #param_type)
+                     -> #constructor_name<#(#generic_args,)* #(#before_tokens,)* (), #(#after_tokens,)*>
+                    {
+                     let #param_name = #value;
+                     // SAFETY: we write to the field via addr_of_mut, unaligned for packed types
+                     unsafe {
+                         ::core::ptr::addr_of_mut!((*#fields_ptr).#member).#write_fn(#param_name);
+                     }
+                     let this = ::core::mem::ManuallyDrop::new(self);
+                     #constructor_name::<#(#generic_args,)* #(#before_tokens,)* (), #(#after_tokens,)*> {
+                         state: unsafe { #krate::__private::State::new(this.state.ptr(), ()) },
+                      }
+                    }
+
+                    /// Construct the value of the field in place with its own constructor
+                    ///
+                    /// This is only callable if the type of the field implements `MemConstruct`
+                    /// and if the field is aligned.
+                    pub fn #construct_name<__F>(self, construct: __F)
+                     -> #constructor_name<#(#generic_args,)* #(#before_tokens,)* (), #(#after_tokens,)*>
+                    where
+                        // The bound is higher ranked to be checked when the function is called
+                        // instead of failing for fields which don't implement `MemConstruct`
+                        for<'__memconstruct> #field_type: #krate::MemConstruct,
+                        __F: for<'__b> #krate::ConstructFn<'__b, #field_type>,
+                    {
+                        #aligned_assertion
+                        // SAFETY: The field is part of the memory behind our pointer, it is
+                        // initialized by the constructor of its type
+                        unsafe {
+                            #krate::construct_raw(
+                                ::core::ptr::addr_of_mut!((*#fields_ptr).#member),
+                                construct,
+                            );
+                        }
+                        let this = ::core::mem::ManuallyDrop::new(self);
+                        #constructor_name::<#(#generic_args,)* #(#before_tokens,)* (), #(#after_tokens,)*> {
+                            state: unsafe { #krate::__private::State::new(this.state.ptr(), ()) },
+                        }
+                    }
+
+                    #[doc = #try_doc]
+                    ///
+                    /// An error drops the fields which were already set and is returned together
+                    /// with the name of the field.
+                    pub fn #try_setter_name<#try_generic>(
+                        self,
+                        #param_name: #try_param_type,
+                    ) -> ::core::result::Result<
+                        #constructor_name<#(#generic_args,)* #(#before_tokens,)* (), #(#after_tokens,)*>,
+                        #krate::FieldError<#try_error>,
+                    > {
+                        match #try_result {
+                            ::core::result::Result::Ok(val) => {
+                                ::core::result::Result::Ok(self.#setter_name(val))
+                            }
+                            ::core::result::Result::Err(error) => ::core::result::Result::Err(
+                                #krate::FieldError::new(#field_name, error),
+                            ),
+                        }
+                    }
+
+                    /// Fallibly construct the value of the field in place with its own
+                    /// constructor
+                    ///
+                    /// An error drops the fields which were already set and is returned together
+                    /// with the name of the field. This is only callable if the type of the field
+                    /// implements `MemConstruct` and if the field is aligned.
+                    pub fn #try_construct_name<__E, __F>(
+                        self,
+                        construct: __F,
+                    ) -> ::core::result::Result<
+                        #constructor_name<#(#generic_args,)* #(#before_tokens,)* (), #(#after_tokens,)*>,
+                        #krate::FieldError<__E>,
+                    >
+                    where
+                        for<'__memconstruct> #field_type: #krate::MemConstruct,
+                        __F: for<'__b> #krate::TryConstructFn<'__b, #field_type, __E>,
+                    {
+                        #aligned_assertion
+                        // SAFETY: The field is part of the memory behind our pointer, it is
+                        // initialized by the constructor of its type if it returns `Ok`
+                        let res = unsafe {
+                            #krate::try_construct_raw(
+                                ::core::ptr::addr_of_mut!((*#fields_ptr).#member),
+                                construct,
+                            )
+                        };
+                        if let ::core::result::Result::Err(error) = res {
+                            return ::core::result::Result::Err(
+                                #krate::FieldError::new(#field_name, error),
+                            );
+                        }
+                        let this = ::core::mem::ManuallyDrop::new(self);
+                        ::core::result::Result::Ok(
+                            #constructor_name::<#(#generic_args,)* #(#before_tokens,)* (), #(#after_tokens,)*> {
+                                state: unsafe {
+                                    #krate::__private::State::new(this.state.ptr(), ())
+                                },
+                            }
+                        )
+                    }
+
+                    /// Initialize the field through its uninitialized memory
+                    ///
+                    /// The passed function has to return the proof of initialization obtained from
+                    /// the slot it was given. This is only callable if the field is aligned.
+                    pub fn #init_name<__F>(self, init: __F)
+                     -> #constructor_name<#(#generic_args,)* #(#before_tokens,)* (), #(#after_tokens,)*>
+                    where
+                        __F: for<'__slot> FnOnce(
+                            #krate::Slot<'__slot, #field_type>,
+                        ) -> #krate::Init<'__slot, #field_type>,
+                    {
+                        #aligned_assertion
+                        // SAFETY: The field is part of the memory behind our pointer and aligned,
+                        // the brand of the slot is unique as the function is generic over it
+                        unsafe {
+                            init(#krate::Slot::new(
+                                ::core::ptr::addr_of_mut!((*#fields_ptr).#member).cast(),
+                            ));
+                        }
+                        let this = ::core::mem::ManuallyDrop::new(self);
+                        #constructor_name::<#(#generic_args,)* #(#before_tokens,)* (), #(#after_tokens,)*> {
+                            state: unsafe { #krate::__private::State::new(this.state.ptr(), ()) },
+                        }
+                    }
+
+                    /// Set the value of the field through the pointer
+                    ///
+                    /// # Safety
+                    ///
+                    /// The passed function has to fully initialize the field behind the pointer.
+                    #unaligned_doc
+                    pub unsafe fn #with_pointer_fn_name(self, init: impl FnOnce(*mut #field_type))
+                     -> #constructor_name<#(#generic_args,)* #(#before_tokens,)* (), #(#after_tokens,)*>
+                    {
+                        init(::core::ptr::addr_of_mut!((*#fields_ptr).#member));
+                        let this = ::core::mem::ManuallyDrop::new(self);
+                        #constructor_name::<#(#generic_args,)* #(#before_tokens,)* (), #(#after_tokens,)*> {
+                            state: unsafe { #krate::__private::State::new(this.state.ptr(), ()) },
+                        }
+                    }
+                }
+            };
+
+            impls.push(impl_quote);
         }
 
-        unsafe impl #impl_generics ::memconstruct::MemConstructConstructor
-            for #constructor_name <#(#construction_tokens,)*> #where_clause
-        {
-            //TODO make this work for generic structs
-            type Target = #name;
+        let finish_impl = self.finish_impl();
+        let drop_impl = self.drop_impl();
 
-            unsafe fn new(ptr: *mut #name) -> Self {
-                Self {
-                    ptr,
-                    boo_scary: ::core::marker::PhantomData::default(),
+        tokens.extend(quote! {
+            #(
+                unsafe impl #krate::__private::FieldToken for #construction_tokens {
+                    const IS_SET: bool = false;
                 }
+            )*
+
+            #(#impls)*
+
+            #finish_impl
+
+            #drop_impl
+        });
+    }
+}
+
+/// The generic parameters of the constructor in front of its token generics, the brand lifetime
+/// followed by the generic parameters of the item without their defaults.
+fn generic_params(generics: &Generics) -> Vec<GenericParam> {
+    let brand: GenericParam = syn::parse_quote! { '__brand };
+    std::iter::once(brand)
+        .chain(generics.params.iter().cloned().map(|mut param| {
+            match &mut param {
+                GenericParam::Type(ty) => {
+                    ty.eq_token = None;
+                    ty.default = None;
+                }
+                GenericParam::Const(konst) => {
+                    konst.eq_token = None;
+                    konst.default = None;
+                }
+                GenericParam::Lifetime(_) => {}
             }
-        }
+            param
+        }))
+        .collect()
+}
+
+/// The generic arguments matching [`generic_params`], without the surrounding angle brackets.
+fn generic_args(generics: &Generics) -> Vec<TokenStream2> {
+    std::iter::once(quote! { '__brand })
+        .chain(generics.params.iter().map(|param| match param {
+            GenericParam::Type(ty) => ty.ident.to_token_stream(),
+            GenericParam::Const(konst) => konst.ident.to_token_stream(),
+            GenericParam::Lifetime(lt) => lt.lifetime.to_token_stream(),
+        }))
+        .collect()
+}
+
+/// The type placed in the `State` of constructors to make them invariant over their brand.
+fn brand_marker() -> TokenStream2 {
+    quote! { fn(&'__brand ()) -> &'__brand () }
+}
+
+fn memconstruct_token(prefix: &str, field_name: &str) -> Ident {
+    quote::format_ident!("Unset_{}{}", prefix, field_name)
+}
+
+/// The name of the module next to the item which holds the typestate markers of its constructors.
+fn markers_module(name: &Ident) -> Ident {
+    quote::format_ident!("{}_memconstruct", snake_case(&name.unraw().to_string()))
+}
 
-        #(#impls)*
+/// Declare the markers module of an item with the same visibility as the item.
+fn declare_markers(name: &Ident, vis: &Visibility, items: TokenStream2) -> TokenStream2 {
+    let markers = markers_module(name);
+    let doc = format!(" The typestate markers of the constructors of `{}`", name.unraw());
+    quote! {
+        #[doc = #doc]
+        #[allow(dead_code, non_camel_case_types, clippy::all)]
+        #vis mod #markers {
+            #items
+        }
     }
 }
 
-fn memconstruct_token(type_name: &Ident, field_name: &Member) -> TokenStream2 {
-    let ident = quote::format_ident!("MemConstruct{}{}", type_name, field_name);
-    quote! { #ident }
+/// Convert a `CamelCase` name to `snake_case`.
+fn snake_case(name: &str) -> String {
+    let chars = name.chars().collect::<Vec<_>>();
+    let mut snake = String::with_capacity(name.len() + 4);
+    for (i, c) in chars.iter().enumerate() {
+        if c.is_uppercase() && i != 0 {
+            let prev = chars[i - 1];
+            let next_lower = chars.get(i + 1).is_some_and(|next| next.is_lowercase());
+            if prev != '_' && (prev.is_lowercase() || prev.is_numeric() || next_lower) {
+                snake.push('_');
+            }
+        }
+        snake.extend(c.to_lowercase());
+    }
+    snake
 }
 
 fn impl_zst(
     name: Ident,
     constructor_name: Ident,
     generics: Generics,
-    zst_constructions: TokenStream2,
-) -> TokenStream2 {
+    vis: Visibility,
+    krate: &Path,
+) -> Expansion {
     let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
-    quote! {
-        unsafe impl ::memconstruct::MemConstruct for #name #ty_generics #where_clause {
-            type Constructor = #constructor_name;
-            type ConstructorFinishedToken = Self::Constructor;
+    let generic_params = generic_params(&generics);
+    let generic_args = generic_args(&generics);
+    let brand_marker = brand_marker();
+    let doc = format!(
+        " Constructs a `{}` in place, generated by `#[derive(MemConstruct)]`.\n\n\
+        The type has no fields so the constructor is always finished.",
+        name,
+    );
+    let exported = quote! {
+        // The pointer is never used, it only carries the generic parameters of the ZST
+        #[doc = #doc]
+        #vis struct #constructor_name <#(#generic_params,)*> #where_clause {
+            state: #krate::__private::State<#name #ty_generics, #brand_marker>,
+        }
+    };
+    let impls = quote! {
+        unsafe impl #impl_generics #krate::MemConstruct for #name #ty_generics
+            #where_clause
+        {
+            type Constructor<'__brand> = #constructor_name <#(#generic_args,)*>;
+            type ConstructorFinishedToken<'__brand> = Self::Constructor<'__brand>;
+        }
 
-            fn new_boxed_zst() -> Box<Self> where Self: Sized {
-                Box::new( #zst_constructions )
+        unsafe impl<#(#generic_params,)*> #krate::MemConstructConstructor for
+            #constructor_name <#(#generic_args,)*> #where_clause
+        {
+            type Target = #name #ty_generics;
+
+            unsafe fn new(ptr: *mut Self::Target) -> Self {
+                Self {
+                    state: unsafe { #krate::__private::State::new(ptr, ()) },
+                }
             }
         }
 
-        pub(crate) struct #constructor_name;
-
-        unsafe impl #impl_generics ::memconstruct::MemConstructConstructor for
-            #constructor_name #ty_generics #where_clause
+        unsafe impl #impl_generics #krate::zeroed::ZeroedConstruct for #name #ty_generics
+            #where_clause
         {
-            type Target = #name #impl_generics ;
+            type ZeroedConstructor<'__brand> = Self::Constructor<'__brand>;
 
-            unsafe fn new(_ptr: *mut Self::Target) -> Self {
-                Self
+            unsafe fn zeroed_constructor<'__brand>(
+                ptr: *mut Self,
+            ) -> Self::ZeroedConstructor<'__brand> {
+                #krate::MemConstructConstructor::new(ptr)
             }
         }
-    }
+    };
+
+    Expansion { exported, impls }
 }
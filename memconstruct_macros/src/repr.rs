@@ -0,0 +1,69 @@
+//! Parsing of the `repr` attribute, the layout of an item decides how its fields are written.
+
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{Attribute, Ident, LitInt};
+
+/// The parts of the `repr` attribute relevant for the layout of an item.
+pub(crate) struct Repr {
+    c: bool,
+    int: Option<Ident>,
+    /// The alignment the fields are packed to, `1` for `#[repr(packed)]`
+    pub(crate) packed: Option<LitInt>,
+}
+
+impl Repr {
+    pub(crate) fn from_attrs(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut repr = Repr {
+            c: false,
+            int: None,
+            packed: None,
+        };
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("repr")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("C") {
+                    repr.c = true;
+                } else if meta.path.is_ident("packed") {
+                    repr.packed = Some(if meta.input.peek(syn::token::Paren) {
+                        let content;
+                        syn::parenthesized!(content in meta.input);
+                        content.parse()?
+                    } else {
+                        LitInt::new("1", meta.path.segments[0].ident.span())
+                    });
+                } else if let Some(ident) = meta.path.get_ident().filter(|ident| is_int(ident)) {
+                    repr.int = Some(ident.clone());
+                } else if meta.input.peek(syn::token::Paren) {
+                    // Skip the arguments of `align(N)` and similar
+                    let _ = meta.input.parse::<proc_macro2::Group>()?;
+                }
+                Ok(())
+            })?;
+        }
+
+        Ok(repr)
+    }
+
+    /// The representation of the tag of an enum, `None` if the layout of the enum is unspecified
+    pub(crate) fn enum_tag(&self) -> Option<TokenStream2> {
+        match (&self.int, self.c) {
+            (Some(int), _) => Some(quote! { #int }),
+            (None, true) => Some(quote! { C }),
+            (None, false) => None,
+        }
+    }
+
+    /// Whether an enum has a primitive representation without `C`, if so the tag is part of the
+    /// struct of each variant instead of being followed by a union of all variants
+    pub(crate) fn tag_in_variants(&self) -> bool {
+        self.int.is_some() && !self.c
+    }
+}
+
+fn is_int(ident: &Ident) -> bool {
+    [
+        "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128", "isize",
+    ]
+    .iter()
+    .any(|int| ident == int)
+}